@@ -0,0 +1,60 @@
+// Copyright 2020 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under the MIT license <LICENSE-MIT
+// https://opensource.org/licenses/MIT> or the Modified BSD license <LICENSE-BSD
+// https://opensource.org/licenses/BSD-3-Clause>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms. Please review the Licences for the
+// specific language governing permissions and limitations relating to use of the SAFE Network
+// Software.
+
+use crate::{Error, Result};
+use serde::{Deserialize, Serialize};
+
+/// Maximum size the value of a compressed Blob may decompress to.
+pub const MAX_DECOMPRESSED_BLOB_SIZE_IN_BYTES: u64 = 16 * 1024 * 1024;
+
+/// Compression level used for Blob values. Changing it changes the serialised form of
+/// compressed Blobs, but not their addresses.
+const COMPRESSION_LEVEL: u8 = 6;
+
+/// Encoding of the value of a serialised Blob.
+#[derive(Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Hash, Serialize, Deserialize, Debug)]
+pub enum Codec {
+    /// Value is stored as is.
+    Raw,
+    /// Value is compressed with DEFLATE.
+    Deflate,
+}
+
+/// Compresses a Blob value with DEFLATE.
+///
+/// Returns `None` if the compressed value would not be smaller than `value`.
+pub(crate) fn compress(value: &[u8]) -> Option<Vec<u8>> {
+    let compressed = miniz_oxide::deflate::compress_to_vec(value, COMPRESSION_LEVEL);
+    if compressed.len() < value.len() {
+        Some(compressed)
+    } else {
+        None
+    }
+}
+
+/// Decompresses a Blob value compressed with DEFLATE.
+///
+/// Returns `Err(Error::FailedToParse)` if the value is invalid, or larger than
+/// `MAX_DECOMPRESSED_BLOB_SIZE_IN_BYTES` once decompressed.
+pub(crate) fn decompress(compressed: &[u8]) -> Result<Vec<u8>> {
+    miniz_oxide::inflate::decompress_to_vec_with_limit(
+        compressed,
+        MAX_DECOMPRESSED_BLOB_SIZE_IN_BYTES as usize,
+    )
+    .map_err(|status| Error::FailedToParse(format!("Invalid compressed Blob: {:?}", status)))
+}
+
+/// Decodes a serialised Blob value, returning the value along with
+/// its compressed form if it was compressed.
+pub(crate) fn decode(codec: Codec, encoded: Vec<u8>) -> Result<(Vec<u8>, Option<Vec<u8>>)> {
+    match codec {
+        Codec::Raw => Ok((encoded, None)),
+        Codec::Deflate => Ok((decompress(&encoded)?, Some(encoded))),
+    }
+}
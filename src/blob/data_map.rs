@@ -0,0 +1,462 @@
+// Copyright 2020 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under the MIT license <LICENSE-MIT
+// https://opensource.org/licenses/MIT> or the Modified BSD license <LICENSE-BSD
+// https://opensource.org/licenses/BSD-3-Clause>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms. Please review the Licences for the
+// specific language governing permissions and limitations relating to use of the SAFE Network
+// Software.
+
+use super::{
+    encryption::{self, Encryption},
+    Address, Data, MerkleTree, PrivateData, PublicData,
+};
+use crate::{utils, BlobRead, Error, PublicKey, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Maximum size of the content of a single chunk.
+/// This leaves room for the serialisation overhead of a Blob,
+/// so that any chunk fits within `MAX_BLOB_SIZE_IN_BYTES`.
+pub const MAX_CHUNK_SIZE_IN_BYTES: usize = 1024 * 1024;
+
+/// What is needed to fetch and verify a single chunk.
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Serialize, Deserialize, Debug)]
+pub struct ChunkInfo {
+    /// Network address of the chunk.
+    pub address: Address,
+    /// Size of the chunk content.
+    pub size: u64,
+    /// SHA3-256 hash of the chunk content.
+    /// This is also the key material of an encrypted chunk.
+    pub hash: [u8; 32],
+}
+
+impl ChunkInfo {
+    // Describes `chunk`, given the content it holds before any encryption.
+    fn new(chunk: &Data, content: &[u8]) -> Self {
+        Self {
+            address: *chunk.address(),
+            size: content.len() as u64,
+            hash: tiny_keccak::sha3_256(content),
+        }
+    }
+
+    /// Returns `Ok(())` if the size and hash of `content` match this chunk,
+    /// and `Err(Error::InvalidChunk)` otherwise.
+    pub fn verify(&self, content: &[u8]) -> Result<()> {
+        if content.len() as u64 == self.size && tiny_keccak::sha3_256(content) == self.hash {
+            Ok(())
+        } else {
+            Err(Error::InvalidChunk)
+        }
+    }
+}
+
+/// The ordered list of chunks that some content was split into.
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Serialize, Deserialize, Debug)]
+pub struct DataMap {
+    /// Size of the content.
+    size: u64,
+    /// The chunks, in content order.
+    chunks: Vec<ChunkInfo>,
+    /// How the chunks are encrypted, if at all.
+    encryption: Option<Encryption>,
+}
+
+impl DataMap {
+    /// Splits `content` into chunks of at most `MAX_CHUNK_SIZE_IN_BYTES`.
+    ///
+    /// The chunks are private Blobs if an owner is given, and public Blobs otherwise.
+    /// Returns the data map together with the chunks to store.
+    pub fn new(content: &[u8], owner: Option<PublicKey>) -> (Self, Vec<Data>) {
+        let (chunks, infos) = content
+            .chunks(MAX_CHUNK_SIZE_IN_BYTES)
+            .map(|bytes| {
+                let chunk = new_chunk(bytes.to_vec(), owner);
+                let info = ChunkInfo::new(&chunk, bytes);
+                (chunk, info)
+            })
+            .unzip();
+        let data_map = Self {
+            size: content.len() as u64,
+            chunks: infos,
+            encryption: None,
+        };
+        (data_map, chunks)
+    }
+
+    /// Splits `content` into encrypted private chunks of at most `MAX_CHUNK_SIZE_IN_BYTES`
+    /// of content each, so that the nodes storing them cannot read them.
+    ///
+    /// Keys are derived from the content and the `secret` if one is given (see `Encryption`).
+    /// Returns the data map together with the chunks to store, or `Err(Error::InvalidChunk)`
+    /// if a chunk cannot be encrypted.
+    pub fn new_encrypted(
+        content: &[u8],
+        owner: PublicKey,
+        secret: Option<&[u8]>,
+    ) -> Result<(Self, Vec<Data>)> {
+        let mut chunks = Vec::new();
+        let mut infos = Vec::new();
+        for bytes in content.chunks(MAX_CHUNK_SIZE_IN_BYTES) {
+            let hash = tiny_keccak::sha3_256(bytes);
+            let encrypted = encryption::encrypt(bytes, &hash, secret)?;
+            let chunk = new_chunk(encrypted, Some(owner));
+            infos.push(ChunkInfo::new(&chunk, bytes));
+            chunks.push(chunk);
+        }
+        let encryption = if secret.is_some() {
+            Encryption::Secret
+        } else {
+            Encryption::Convergent
+        };
+        let data_map = Self {
+            size: content.len() as u64,
+            chunks: infos,
+            encryption: Some(encryption),
+        };
+        Ok((data_map, chunks))
+    }
+
+    /// Returns the size of the content.
+    pub fn size(&self) -> u64 {
+        self.size
+    }
+
+    /// Returns the chunks, in content order.
+    pub fn chunks(&self) -> &[ChunkInfo] {
+        &self.chunks
+    }
+
+    /// Returns the Merkle tree over the names of the chunks, in content order.
+    pub fn merkle_tree(&self) -> MerkleTree {
+        let names: Vec<_> = self
+            .chunks
+            .iter()
+            .map(|info| *info.address.name())
+            .collect();
+        MerkleTree::new(&names)
+    }
+
+    /// Returns how the chunks are encrypted, if at all.
+    pub fn encryption(&self) -> Option<Encryption> {
+        self.encryption
+    }
+
+    /// Returns the queries to get `len` bytes of the content, starting at `offset`,
+    /// in content order. Concatenating their responses gives the requested range.
+    ///
+    /// Ranges cannot be verified against the data map, nor decrypted, so this returns
+    /// `Err(Error::InvalidOperation)` for encrypted content, and `Err(Error::InvalidRange)`
+    /// if the range is not within the content.
+    pub fn range_queries(&self, offset: u64, len: u64) -> Result<Vec<BlobRead>> {
+        if self.encryption.is_some() {
+            return Err(Error::InvalidOperation);
+        }
+        let end = match offset.checked_add(len) {
+            Some(end) if end <= self.size => end,
+            _ => return Err(Error::InvalidRange(self.size)),
+        };
+
+        let mut queries = Vec::new();
+        let mut chunk_start = 0;
+        for info in &self.chunks {
+            let chunk_end = chunk_start + info.size;
+            if chunk_end > offset && chunk_start < end {
+                let range_start = offset.max(chunk_start);
+                let range_end = end.min(chunk_end);
+                queries.push(BlobRead::GetRange {
+                    address: info.address,
+                    offset: range_start - chunk_start,
+                    len: range_end - range_start,
+                });
+            }
+            chunk_start = chunk_end;
+        }
+        Ok(queries)
+    }
+
+    /// Reassembles the content from the fetched chunks, which can be in any order,
+    /// decrypting them if they are encrypted without a user secret.
+    ///
+    /// Returns `Err(Error::NoSuchData)` if a chunk is missing,
+    /// `Err(Error::InvalidChunk)` if a chunk does not match the data map, and
+    /// `Err(Error::AccessDenied)` if the chunks are encrypted with a user secret.
+    pub fn assemble(&self, chunks: &[Data]) -> Result<Vec<u8>> {
+        self.assemble_with(chunks, None)
+    }
+
+    /// Reassembles the content from the fetched chunks, which can be in any order,
+    /// decrypting them with the given user secret.
+    ///
+    /// Returns the same errors as `assemble`.
+    pub fn assemble_with_secret(&self, chunks: &[Data], secret: &[u8]) -> Result<Vec<u8>> {
+        self.assemble_with(chunks, Some(secret))
+    }
+
+    fn assemble_with(&self, chunks: &[Data], secret: Option<&[u8]>) -> Result<Vec<u8>> {
+        let chunks: BTreeMap<_, _> = chunks
+            .iter()
+            .map(|chunk| (*chunk.address(), chunk.value()))
+            .collect();
+        // The size comes from the data map, which may not be trusted,
+        // so never allocate more than the chunks supplied.
+        let supplied: usize = chunks.values().map(|value| value.len()).sum();
+        let mut content = Vec::with_capacity(supplied.min(self.size as usize));
+        for info in &self.chunks {
+            let value = chunks.get(&info.address).ok_or(Error::NoSuchData)?;
+            let value = match self.encryption {
+                None => value.to_vec(),
+                Some(Encryption::Convergent) => encryption::decrypt(value, &info.hash, None)?,
+                Some(Encryption::Secret) => {
+                    let secret = secret.ok_or(Error::AccessDenied)?;
+                    encryption::decrypt(value, &info.hash, Some(secret))?
+                }
+            };
+            info.verify(&value)?;
+            content.extend_from_slice(&value);
+            if content.len() as u64 > self.size {
+                return Err(Error::InvalidChunk);
+            }
+        }
+        if content.len() as u64 != self.size {
+            return Err(Error::InvalidChunk);
+        }
+        Ok(content)
+    }
+}
+
+/// The content of the root Blob of some chunked content.
+#[derive(Clone, Eq, PartialEq, Hash, Serialize, Deserialize, Debug)]
+pub enum DataMapLevel {
+    /// Data map of the content itself.
+    First(DataMap),
+    /// Data map of a serialised `DataMapLevel`. Used when
+    /// a data map is too large to fit in a single Blob.
+    Additional(DataMap),
+}
+
+impl DataMapLevel {
+    /// Splits `content` into chunks, and stores its data map in a root Blob.
+    /// The data map is chunked in turn, for as long as it doesn't fit in the root Blob.
+    ///
+    /// Returns the root Blob, followed by all the other chunks to store.
+    pub fn pack(content: &[u8], owner: Option<PublicKey>) -> (Data, Vec<Data>) {
+        let (data_map, mut chunks) = DataMap::new(content, owner);
+        let mut level = DataMapLevel::First(data_map);
+        loop {
+            let serialised = utils::serialise(&level);
+            let root = new_chunk(serialised, owner);
+            if root.validate_size() {
+                return (root, chunks);
+            }
+            let (data_map, more_chunks) = DataMap::new(root.value(), owner);
+            chunks.extend(more_chunks);
+            level = DataMapLevel::Additional(data_map);
+        }
+    }
+
+    /// Reads the data map stored in a root Blob, or in the
+    /// content reassembled from an `Additional` data map.
+    pub fn unpack(content: &[u8]) -> Result<Self> {
+        bincode::deserialize(content).map_err(|e| Error::FailedToParse(e.to_string()))
+    }
+
+    /// Reassembles and verifies the content packed by `pack`, going through the `Additional`
+    /// data maps down to the content. The chunks of each data map are fetched with `fetch`.
+    ///
+    /// Returns the errors of `unpack` and `DataMap::assemble`, and any error from `fetch`.
+    pub fn reassemble<F>(root: &Data, mut fetch: F) -> Result<Vec<u8>>
+    where
+        F: FnMut(&Address) -> Result<Data>,
+    {
+        let mut level = Self::unpack(root.value())?;
+        loop {
+            let data_map = match level {
+                DataMapLevel::First(ref data_map) | DataMapLevel::Additional(ref data_map) => {
+                    data_map
+                }
+            };
+            let chunks = data_map
+                .chunks()
+                .iter()
+                .map(|info| fetch(&info.address))
+                .collect::<Result<Vec<_>>>()?;
+            let content = data_map.assemble(&chunks)?;
+            match level {
+                DataMapLevel::First(_) => return Ok(content),
+                DataMapLevel::Additional(_) => level = Self::unpack(&content)?,
+            }
+        }
+    }
+}
+
+fn new_chunk(value: Vec<u8>, owner: Option<PublicKey>) -> Data {
+    match owner {
+        Some(owner) => PrivateData::new(value, owner).into(),
+        None => PublicData::new(value).into(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{new_chunk, DataMap, DataMapLevel, Encryption, MAX_CHUNK_SIZE_IN_BYTES};
+    use crate::{utils, BlobRead, Error, PublicBlob, PublicKey};
+    use rand::{self, Rng};
+    use std::{collections::BTreeMap, iter};
+    use threshold_crypto::SecretKey;
+    use unwrap::unwrap;
+
+    fn random_content(len: usize) -> Vec<u8> {
+        let mut rng = rand::thread_rng();
+        iter::repeat_with(|| rng.gen()).take(len).collect()
+    }
+
+    #[test]
+    fn split_and_assemble() {
+        let content = random_content(2 * MAX_CHUNK_SIZE_IN_BYTES + 100);
+        let owner = PublicKey::Bls(SecretKey::random().public_key());
+        let (data_map, mut chunks) = DataMap::new(&content, Some(owner));
+        assert_eq!(data_map.chunks().len(), 3);
+        assert!(chunks
+            .iter()
+            .all(|chunk| chunk.validate_size() && chunk.is_unpub()));
+
+        chunks.reverse();
+        assert_eq!(unwrap!(data_map.assemble(&chunks)), content);
+
+        let _ = chunks.pop();
+        assert_eq!(data_map.assemble(&chunks), Err(Error::NoSuchData));
+    }
+
+    #[test]
+    fn assemble_rejects_corrupted_chunks() {
+        let content = random_content(MAX_CHUNK_SIZE_IN_BYTES + 1);
+        let (mut data_map, mut chunks) = DataMap::new(&content, None);
+        data_map.chunks[1].hash = [0; 32];
+        assert_eq!(data_map.assemble(&chunks), Err(Error::InvalidChunk));
+
+        chunks[1] = PublicBlob::new(vec![0; 1]).into();
+        assert_eq!(data_map.assemble(&chunks), Err(Error::NoSuchData));
+    }
+
+    #[test]
+    fn assemble_rejects_wrong_size() {
+        let content = random_content(MAX_CHUNK_SIZE_IN_BYTES + 1);
+        let (mut data_map, chunks) = DataMap::new(&content, None);
+        data_map.size = u64::MAX;
+        assert_eq!(data_map.assemble(&chunks), Err(Error::InvalidChunk));
+
+        data_map.size = 1;
+        assert_eq!(data_map.assemble(&chunks), Err(Error::InvalidChunk));
+    }
+
+    #[test]
+    fn range_queries() {
+        let content = random_content(2 * MAX_CHUNK_SIZE_IN_BYTES + 100);
+        let (data_map, chunks) = DataMap::new(&content, None);
+
+        let offset = MAX_CHUNK_SIZE_IN_BYTES as u64 - 10;
+        let len = MAX_CHUNK_SIZE_IN_BYTES as u64 + 50;
+        let queries = unwrap!(data_map.range_queries(offset, len));
+        assert_eq!(queries.len(), 3);
+        let range: Vec<u8> = queries
+            .iter()
+            .flat_map(|query| match query {
+                BlobRead::GetRange {
+                    address,
+                    offset,
+                    len,
+                } => {
+                    let chunk = unwrap!(chunks.iter().find(|chunk| chunk.address() == address));
+                    unwrap!(chunk.range(*offset, *len)).to_vec()
+                }
+                BlobRead::Get(_) => panic!("Unexpected query {:?}", query),
+            })
+            .collect();
+        assert_eq!(range, &content[offset as usize..(offset + len) as usize]);
+
+        let size = content.len() as u64;
+        assert_eq!(
+            data_map.range_queries(size, 1),
+            Err(Error::InvalidRange(size))
+        );
+    }
+
+    #[test]
+    fn pack_and_unpack() {
+        let content = random_content(3 * MAX_CHUNK_SIZE_IN_BYTES);
+        let (root, chunks) = DataMapLevel::pack(&content, None);
+        assert!(root.validate_size());
+        match unwrap!(DataMapLevel::unpack(root.value())) {
+            DataMapLevel::First(data_map) => {
+                assert_eq!(data_map.size(), content.len() as u64);
+                assert_eq!(unwrap!(data_map.assemble(&chunks)), content);
+            }
+            DataMapLevel::Additional(_) => panic!("Unexpected additional data map"),
+        }
+    }
+
+    #[test]
+    fn reassemble_through_additional_level() {
+        let content = random_content(MAX_CHUNK_SIZE_IN_BYTES + 100);
+        let (data_map, mut chunks) = DataMap::new(&content, None);
+        // Data maps this large would take too long to build, so the additional level is made
+        // directly.
+        let first = utils::serialise(&DataMapLevel::First(data_map));
+        let (data_map, more_chunks) = DataMap::new(&first, None);
+        chunks.extend(more_chunks);
+        let root = new_chunk(utils::serialise(&DataMapLevel::Additional(data_map)), None);
+
+        let stored: BTreeMap<_, _> = chunks
+            .into_iter()
+            .map(|chunk| (*chunk.address(), chunk))
+            .collect();
+        let content_again = unwrap!(DataMapLevel::reassemble(&root, |address| {
+            stored.get(address).cloned().ok_or(Error::NoSuchData)
+        }));
+        assert_eq!(content_again, content);
+
+        let (root, _) = DataMapLevel::pack(&content, None);
+        assert_eq!(
+            DataMapLevel::reassemble(&root, |_| Err(Error::NoSuchData)),
+            Err(Error::NoSuchData)
+        );
+    }
+
+    #[test]
+    fn encrypted_split_and_assemble() {
+        let content = random_content(MAX_CHUNK_SIZE_IN_BYTES + 100);
+        let owner = PublicKey::Bls(SecretKey::random().public_key());
+
+        let (data_map, chunks) = unwrap!(DataMap::new_encrypted(&content, owner, None));
+        assert_eq!(data_map.encryption(), Some(Encryption::Convergent));
+        assert!(chunks.iter().all(|chunk| chunk.is_unpub()));
+        assert_ne!(&chunks[0].value()[..100], &content[..100]);
+        assert_eq!(unwrap!(data_map.assemble(&chunks)), content);
+
+        // Identical content gives identical chunks.
+        let (same_data_map, same_chunks) = unwrap!(DataMap::new_encrypted(&content, owner, None));
+        assert_eq!(same_data_map, data_map);
+        assert_eq!(same_chunks, chunks);
+    }
+
+    #[test]
+    fn secret_is_needed_to_assemble() {
+        let content = random_content(100);
+        let owner = PublicKey::Bls(SecretKey::random().public_key());
+        let (data_map, chunks) = unwrap!(DataMap::new_encrypted(&content, owner, Some(b"secret")));
+        assert_eq!(data_map.encryption(), Some(Encryption::Secret));
+
+        assert_eq!(data_map.assemble(&chunks), Err(Error::AccessDenied));
+        assert_eq!(
+            data_map.assemble_with_secret(&chunks, b"wrong"),
+            Err(Error::InvalidChunk)
+        );
+        assert_eq!(
+            unwrap!(data_map.assemble_with_secret(&chunks, b"secret")),
+            content
+        );
+    }
+}
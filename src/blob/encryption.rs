@@ -0,0 +1,70 @@
+// Copyright 2020 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under the MIT license <LICENSE-MIT
+// https://opensource.org/licenses/MIT> or the Modified BSD license <LICENSE-BSD
+// https://opensource.org/licenses/BSD-3-Clause>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms. Please review the Licences for the
+// specific language governing permissions and limitations relating to use of the SAFE Network
+// Software.
+
+use crate::{Error, Result};
+use aes_gcm::{
+    aead::{generic_array::GenericArray, Aead, NewAead},
+    Aes256Gcm,
+};
+use serde::{Deserialize, Serialize};
+
+/// Length of an AES-GCM nonce.
+const NONCE_LEN: usize = 12;
+
+/// How the keys for encrypting chunks are derived.
+///
+/// Either way, the key of a chunk is derived from the hash of its content, which is
+/// recorded in the data map. Chunks are authenticated, and fail to decrypt if tampered with.
+#[derive(Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Hash, Serialize, Deserialize, Debug)]
+pub enum Encryption {
+    /// The key is derived from the content hash alone, so that identical content
+    /// gives identical chunks. Anyone holding the data map can decrypt the chunks,
+    /// so it must be kept private.
+    Convergent,
+    /// The key is derived from the content hash and a secret of the user.
+    /// The data map alone is not enough to decrypt the chunks.
+    Secret,
+}
+
+/// Encrypts chunk content, given the hash of the content and an optional user secret.
+///
+/// Returns `Err(Error::InvalidChunk)` if the content cannot be encrypted.
+pub(crate) fn encrypt(content: &[u8], hash: &[u8; 32], secret: Option<&[u8]>) -> Result<Vec<u8>> {
+    let (key, nonce) = derive_key_and_nonce(hash, secret);
+    let cipher = Aes256Gcm::new(GenericArray::from_slice(&key));
+    cipher
+        .encrypt(GenericArray::from_slice(&nonce[..NONCE_LEN]), content)
+        .map_err(|_| Error::InvalidChunk)
+}
+
+/// Decrypts chunk content, given the hash of the content and an optional user secret.
+///
+/// Returns `Err(Error::InvalidChunk)` if the content cannot be authenticated.
+pub(crate) fn decrypt(encrypted: &[u8], hash: &[u8; 32], secret: Option<&[u8]>) -> Result<Vec<u8>> {
+    let (key, nonce) = derive_key_and_nonce(hash, secret);
+    let cipher = Aes256Gcm::new(GenericArray::from_slice(&key));
+    cipher
+        .decrypt(GenericArray::from_slice(&nonce[..NONCE_LEN]), encrypted)
+        .map_err(|_| Error::InvalidChunk)
+}
+
+// The key is unique to the content (and secret), so a nonce derived from it is never reused
+// with different content. This keeps encryption deterministic, as needed for convergence.
+fn derive_key_and_nonce(hash: &[u8; 32], secret: Option<&[u8]>) -> ([u8; 32], [u8; 32]) {
+    let key = match secret {
+        Some(secret) => {
+            let mut material = secret.to_vec();
+            material.extend_from_slice(hash);
+            tiny_keccak::sha3_256(&material)
+        }
+        None => tiny_keccak::sha3_256(hash),
+    };
+    let nonce = tiny_keccak::sha3_256(&key);
+    (key, nonce)
+}
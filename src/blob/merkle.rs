@@ -0,0 +1,224 @@
+// Copyright 2020 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under the MIT license <LICENSE-MIT
+// https://opensource.org/licenses/MIT> or the Modified BSD license <LICENSE-BSD
+// https://opensource.org/licenses/BSD-3-Clause>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms. Please review the Licences for the
+// specific language governing permissions and limitations relating to use of the SAFE Network
+// Software.
+
+use crate::XorName;
+use serde::{Deserialize, Serialize};
+
+// Domain separation of leaves, inner nodes and the root, so that an inner node cannot pass for
+// a leaf, nor the top node of a tree for its root.
+const LEAF_PREFIX: u8 = 0;
+const NODE_PREFIX: u8 = 1;
+const ROOT_PREFIX: u8 = 2;
+
+/// Merkle tree over the names of the chunks of some content, in content order.
+///
+/// A node without a sibling is carried up to the next level as is. The root commits to the
+/// number of leaves as well as to the top node, so that proofs cannot claim another position.
+#[derive(Clone, Eq, PartialEq, Hash, Serialize, Deserialize, Debug)]
+pub struct MerkleTree {
+    /// Hashes of each level, from the leaves up to the root.
+    levels: Vec<Vec<[u8; 32]>>,
+}
+
+impl MerkleTree {
+    /// Builds the tree over `names`.
+    pub fn new(names: &[XorName]) -> Self {
+        let mut levels = vec![names.iter().map(leaf_hash).collect::<Vec<_>>()];
+        loop {
+            let level = &levels[levels.len() - 1];
+            if level.len() <= 1 {
+                break;
+            }
+            let next = level
+                .chunks(2)
+                .map(|pair| match pair {
+                    [left, right] => node_hash(left, right),
+                    _ => pair[0],
+                })
+                .collect();
+            levels.push(next);
+        }
+        Self { levels }
+    }
+
+    /// Returns the number of leaves.
+    pub fn len(&self) -> usize {
+        self.levels.first().map_or(0, Vec::len)
+    }
+
+    /// Returns true if the tree has no leaves.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the root hash, over the number of leaves and the top node.
+    /// The top node of an empty tree is the hash of no data.
+    pub fn root(&self) -> [u8; 32] {
+        let top = self
+            .levels
+            .last()
+            .and_then(|level| level.first())
+            .copied()
+            .unwrap_or_else(|| tiny_keccak::sha3_256(&[]));
+        root_hash(self.len() as u64, &top)
+    }
+
+    /// Returns the proof that the leaf at `index` is included in the tree,
+    /// or `None` if there is no such leaf.
+    pub fn proof(&self, index: usize) -> Option<MerkleProof> {
+        if index >= self.len() {
+            return None;
+        }
+        let mut position = index;
+        let mut siblings = Vec::new();
+        for level in &self.levels[..self.levels.len() - 1] {
+            if let Some(sibling) = level.get(position ^ 1) {
+                siblings.push(*sibling);
+            }
+            position /= 2;
+        }
+        Some(MerkleProof {
+            index: index as u64,
+            leaf_count: self.len() as u64,
+            siblings,
+        })
+    }
+}
+
+/// Proof that a chunk name is included in a `MerkleTree`.
+#[derive(Clone, Eq, PartialEq, Hash, Serialize, Deserialize, Debug)]
+pub struct MerkleProof {
+    /// Index of the leaf.
+    index: u64,
+    /// Number of leaves in the tree.
+    leaf_count: u64,
+    /// Hashes of the siblings on the path from the leaf to the root.
+    siblings: Vec<[u8; 32]>,
+}
+
+impl MerkleProof {
+    /// Returns the index of the leaf, which is the position of the chunk in content order.
+    pub fn index(&self) -> u64 {
+        self.index
+    }
+
+    /// Returns true if this proves that `name` is the leaf at `index` of the tree with `root`.
+    pub fn verify(&self, name: &XorName, root: &[u8; 32]) -> bool {
+        if self.index >= self.leaf_count {
+            return false;
+        }
+        let mut hash = leaf_hash(name);
+        let mut position = self.index;
+        let mut count = self.leaf_count;
+        let mut siblings = self.siblings.iter();
+        while count > 1 {
+            if position ^ 1 < count {
+                let sibling = match siblings.next() {
+                    Some(sibling) => sibling,
+                    None => return false,
+                };
+                hash = if position % 2 == 0 {
+                    node_hash(&hash, sibling)
+                } else {
+                    node_hash(sibling, &hash)
+                };
+            }
+            position /= 2;
+            count = (count + 1) / 2;
+        }
+        siblings.next().is_none() && root_hash(self.leaf_count, &hash) == *root
+    }
+}
+
+fn leaf_hash(name: &XorName) -> [u8; 32] {
+    let mut hasher = tiny_keccak::Keccak::new_sha3_256();
+    hasher.update(&[LEAF_PREFIX]);
+    hasher.update(&name.0);
+    let mut hash = [0; 32];
+    hasher.finalize(&mut hash);
+    hash
+}
+
+fn node_hash(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+    let mut hasher = tiny_keccak::Keccak::new_sha3_256();
+    hasher.update(&[NODE_PREFIX]);
+    hasher.update(left);
+    hasher.update(right);
+    let mut hash = [0; 32];
+    hasher.finalize(&mut hash);
+    hash
+}
+
+fn root_hash(leaf_count: u64, top: &[u8; 32]) -> [u8; 32] {
+    let mut hasher = tiny_keccak::Keccak::new_sha3_256();
+    hasher.update(&[ROOT_PREFIX]);
+    hasher.update(&leaf_count.to_be_bytes());
+    hasher.update(top);
+    let mut hash = [0; 32];
+    hasher.finalize(&mut hash);
+    hash
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{MerkleProof, MerkleTree};
+    use crate::{utils, XorName};
+    use unwrap::unwrap;
+
+    #[test]
+    fn all_leaves_are_proven() {
+        for len in 1..10 {
+            let names: Vec<_> = (0..len).map(|_| XorName(rand::random())).collect();
+            let tree = MerkleTree::new(&names);
+            let root = tree.root();
+            for (index, name) in names.iter().enumerate() {
+                let proof = unwrap!(tree.proof(index));
+                assert!(proof.verify(name, &root));
+                assert!(!proof.verify(&XorName(rand::random()), &root));
+                if len > 1 {
+                    let other = (index + 1) % len;
+                    assert!(!proof.verify(&names[other], &root));
+                }
+            }
+            assert!(tree.proof(len).is_none());
+        }
+    }
+
+    #[test]
+    fn serialisation() {
+        let names: Vec<_> = (0..5).map(|_| XorName(rand::random())).collect();
+        let tree = MerkleTree::new(&names);
+        let parsed: MerkleTree = unwrap!(bincode::deserialize(&utils::serialise(&tree)));
+        assert_eq!(parsed.root(), tree.root());
+        assert!(unwrap!(parsed.proof(3)).verify(&names[3], &tree.root()));
+        assert_ne!(MerkleTree::new(&[]).root(), tree.root());
+
+        // As could be deserialised from untrusted bytes.
+        let empty = MerkleTree { levels: vec![] };
+        assert!(empty.is_empty());
+        assert!(empty.proof(0).is_none());
+        assert_eq!(empty.root(), MerkleTree::new(&[]).root());
+    }
+
+    #[test]
+    fn proof_cannot_claim_another_position() {
+        let names: Vec<_> = (0..3).map(|_| XorName(rand::random())).collect();
+        let tree = MerkleTree::new(&names);
+        let root = tree.root();
+
+        // The top node of [a, b] is a sibling on the path of c in [a, b, c].
+        let forged = MerkleProof {
+            index: 1,
+            leaf_count: 2,
+            siblings: vec![MerkleTree::new(&names[..2]).levels[1][0]],
+        };
+        assert!(!forged.verify(&names[2], &root));
+        assert!(unwrap!(tree.proof(2)).verify(&names[2], &root));
+    }
+}
@@ -0,0 +1,40 @@
+// Copyright 2020 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under the MIT license <LICENSE-MIT
+// https://opensource.org/licenses/MIT> or the Modified BSD license <LICENSE-BSD
+// https://opensource.org/licenses/BSD-3-Clause>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms. Please review the Licences for the
+// specific language governing permissions and limitations relating to use of the SAFE Network
+// Software.
+
+use bincode::serialized_size;
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, u64};
+
+/// Maximum allowed size for serialised Blob metadata.
+pub const MAX_METADATA_SIZE_IN_BYTES: u64 = 4 * 1024;
+
+/// Describes the content of a Blob.
+#[derive(Clone, Default, Eq, PartialEq, Ord, PartialOrd, Hash, Serialize, Deserialize, Debug)]
+pub struct Metadata {
+    /// MIME type of the content.
+    pub content_type: Option<String>,
+    /// Original name of the file the content was read from.
+    pub file_name: Option<String>,
+    /// Creation time of the content, in seconds since the Unix epoch.
+    pub created: Option<u64>,
+    /// Arbitrary attributes.
+    pub attributes: BTreeMap<String, String>,
+}
+
+impl Metadata {
+    /// Returns size of this metadata after serialisation.
+    pub fn serialised_size(&self) -> u64 {
+        serialized_size(self).unwrap_or(u64::MAX)
+    }
+
+    /// Returns `true` if the size is valid.
+    pub fn validate_size(&self) -> bool {
+        self.serialised_size() <= MAX_METADATA_SIZE_IN_BYTES
+    }
+}
@@ -0,0 +1,873 @@
+// Copyright 2019 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under the MIT license <LICENSE-MIT
+// https://opensource.org/licenses/MIT> or the Modified BSD license <LICENSE-BSD
+// https://opensource.org/licenses/BSD-3-Clause>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms. Please review the Licences for the
+// specific language governing permissions and limitations relating to use of the SAFE Network
+// Software.
+
+mod compression;
+mod data_map;
+mod encryption;
+mod merkle;
+mod metadata;
+mod shard;
+
+pub use compression::{Codec, MAX_DECOMPRESSED_BLOB_SIZE_IN_BYTES};
+pub use data_map::{ChunkInfo, DataMap, DataMapLevel, MAX_CHUNK_SIZE_IN_BYTES};
+pub use encryption::Encryption;
+pub use merkle::{MerkleProof, MerkleTree};
+pub use metadata::{Metadata, MAX_METADATA_SIZE_IN_BYTES};
+pub use shard::{Shard, ShardAddress};
+
+use crate::{utils, Error, PublicKey, XorName};
+use bincode::serialized_size;
+use bytes::Bytes;
+use multibase::Decodable;
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+use std::{
+    cmp::Ordering,
+    fmt::{self, Debug, Formatter},
+    hash::{Hash, Hasher},
+    io::{self, Read},
+    u64,
+};
+
+/// Maximum allowed size for a serialised Blob to grow to.
+pub const MAX_BLOB_SIZE_IN_BYTES: u64 = 1024 * 1024 + 10 * 1024;
+
+/// Private Blob: an immutable chunk of data which can be deleted. Can only be fetched
+/// by the listed owner.
+#[derive(Clone)]
+pub struct PrivateData {
+    /// Network address. Omitted when serialising and calculated from the `value` and `owner` when
+    /// deserialising.
+    address: Address,
+    /// Contained data.
+    value: Bytes,
+    /// Compressed form of `value`, if the Blob is stored compressed.
+    compressed: Option<Bytes>,
+    /// Description of the value.
+    metadata: Option<Metadata>,
+    /// Contains a set of owners of this data. DataManagers enforce that a DELETE or OWNED-GET type
+    /// of request is coming from the MaidManager Authority of the owners.
+    owner: PublicKey,
+}
+
+impl PrivateData {
+    /// Creates a new instance of `PrivateData`.
+    pub fn new(value: Vec<u8>, owner: PublicKey) -> Self {
+        Self::from_parts(value.into(), None, None, owner)
+    }
+
+    /// Creates a new instance of `PrivateData` which is stored compressed, unless compression
+    /// would make it larger. It has the same address as the uncompressed instance.
+    pub fn new_compressed(value: Vec<u8>, owner: PublicKey) -> Self {
+        let compressed = compression::compress(&value).map(Bytes::from);
+        Self::from_parts(value.into(), compressed, None, owner)
+    }
+
+    /// Creates a new instance of `PrivateData` with the content of `reader`,
+    /// which is hashed while being read.
+    ///
+    /// Fails with `io::ErrorKind::InvalidData` if the content is larger than
+    /// `MAX_BLOB_SIZE_IN_BYTES`.
+    pub fn from_reader<R: Read>(reader: R, owner: PublicKey) -> io::Result<Self> {
+        let mut value = Vec::new();
+        let hash_of_value = hash_reader(reader, |bytes| value.extend_from_slice(bytes))?;
+        Ok(Self {
+            address: Address::private(hash_of_value, &owner, None),
+            value: value.into(),
+            compressed: None,
+            metadata: None,
+            owner,
+        })
+    }
+
+    /// Attaches `metadata` to this instance. The metadata is covered by the address.
+    pub fn with_metadata(self, metadata: Metadata) -> Self {
+        Self::from_parts(self.value, self.compressed, Some(metadata), self.owner)
+    }
+
+    fn from_parts(
+        value: Bytes,
+        compressed: Option<Bytes>,
+        metadata: Option<Metadata>,
+        owner: PublicKey,
+    ) -> Self {
+        let hash_of_value = tiny_keccak::sha3_256(&value);
+        Self {
+            address: Address::private(hash_of_value, &owner, metadata.as_ref()),
+            value,
+            compressed,
+            metadata,
+            owner,
+        }
+    }
+
+    /// Returns the value, decompressed if stored compressed.
+    /// The value is reference counted, so cloning it is cheap.
+    pub fn value(&self) -> &Bytes {
+        &self.value
+    }
+
+    /// Returns the encoding the value is stored with.
+    pub fn codec(&self) -> Codec {
+        codec(&self.compressed)
+    }
+
+    /// Returns the metadata.
+    pub fn metadata(&self) -> Option<&Metadata> {
+        self.metadata.as_ref()
+    }
+
+    /// Returns the set of owners.
+    pub fn owner(&self) -> &PublicKey {
+        &self.owner
+    }
+
+    /// Returns the address.
+    pub fn address(&self) -> &Address {
+        &self.address
+    }
+
+    /// Returns the name.
+    pub fn name(&self) -> &XorName {
+        self.address.name()
+    }
+
+    /// Returns size of contained value.
+    pub fn payload_size(&self) -> usize {
+        self.value.len()
+    }
+
+    /// Returns size of this data after serialisation.
+    pub fn serialised_size(&self) -> u64 {
+        serialized_size(self).unwrap_or(u64::MAX)
+    }
+
+    /// Returns `true` if the size is valid.
+    pub fn validate_size(&self) -> bool {
+        self.serialised_size() <= MAX_BLOB_SIZE_IN_BYTES && validate_metadata_size(&self.metadata)
+    }
+}
+
+/// Serialised as `(codec, value, metadata, owner)`, where `value` is encoded according to `codec`.
+/// This replaces the `(value, owner)` layout of earlier versions, which no longer deserialises.
+impl Serialize for PrivateData {
+    fn serialize<S: Serializer>(&self, serialiser: S) -> Result<S::Ok, S::Error> {
+        (
+            self.codec(),
+            encoded(&self.value, &self.compressed),
+            &self.metadata,
+            &self.owner,
+        )
+            .serialize(serialiser)
+    }
+}
+
+impl<'de> Deserialize<'de> for PrivateData {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let (codec, encoded, metadata, owner): (Codec, Vec<u8>, Option<Metadata>, PublicKey) =
+            Deserialize::deserialize(deserializer)?;
+        let (value, compressed) = compression::decode(codec, encoded).map_err(de::Error::custom)?;
+        Ok(PrivateData::from_parts(
+            value.into(),
+            compressed.map(Bytes::from),
+            metadata,
+            owner,
+        ))
+    }
+}
+
+impl Debug for PrivateData {
+    fn fmt(&self, formatter: &mut Formatter) -> fmt::Result {
+        // TODO: Output owners?
+        write!(formatter, "PrivateBlob {:?}", self.name())
+    }
+}
+
+/// Public Blob: an immutable chunk of data which cannot be deleted.
+#[derive(Clone)]
+pub struct PublicData {
+    /// Network address. Omitted when serialising and calculated from the `value` and `metadata`
+    /// when deserialising.
+    address: Address,
+    /// Contained data.
+    value: Bytes,
+    /// Compressed form of `value`, if the Blob is stored compressed.
+    compressed: Option<Bytes>,
+    /// Description of the value.
+    metadata: Option<Metadata>,
+}
+
+impl PublicData {
+    /// Creates a new instance of `Blob`.
+    pub fn new(value: Vec<u8>) -> Self {
+        Self::from_parts(value.into(), None, None)
+    }
+
+    /// Creates a new instance of `Blob` which is stored compressed, unless compression
+    /// would make it larger. It has the same address as the uncompressed instance.
+    pub fn new_compressed(value: Vec<u8>) -> Self {
+        let compressed = compression::compress(&value).map(Bytes::from);
+        Self::from_parts(value.into(), compressed, None)
+    }
+
+    /// Creates a new instance of `Blob` with the content of `reader`,
+    /// which is hashed while being read.
+    ///
+    /// Fails with `io::ErrorKind::InvalidData` if the content is larger than
+    /// `MAX_BLOB_SIZE_IN_BYTES`.
+    pub fn from_reader<R: Read>(reader: R) -> io::Result<Self> {
+        let mut value = Vec::new();
+        let hash_of_value = hash_reader(reader, |bytes| value.extend_from_slice(bytes))?;
+        Ok(Self {
+            address: Address::public(hash_of_value, None),
+            value: value.into(),
+            compressed: None,
+            metadata: None,
+        })
+    }
+
+    /// Attaches `metadata` to this instance. The metadata is covered by the address.
+    pub fn with_metadata(self, metadata: Metadata) -> Self {
+        Self::from_parts(self.value, self.compressed, Some(metadata))
+    }
+
+    fn from_parts(value: Bytes, compressed: Option<Bytes>, metadata: Option<Metadata>) -> Self {
+        let hash_of_value = tiny_keccak::sha3_256(&value);
+        Self {
+            address: Address::public(hash_of_value, metadata.as_ref()),
+            value,
+            compressed,
+            metadata,
+        }
+    }
+
+    /// Returns the value, decompressed if stored compressed.
+    /// The value is reference counted, so cloning it is cheap.
+    pub fn value(&self) -> &Bytes {
+        &self.value
+    }
+
+    /// Returns the encoding the value is stored with.
+    pub fn codec(&self) -> Codec {
+        codec(&self.compressed)
+    }
+
+    /// Returns the metadata.
+    pub fn metadata(&self) -> Option<&Metadata> {
+        self.metadata.as_ref()
+    }
+
+    /// Returns the address.
+    pub fn address(&self) -> &Address {
+        &self.address
+    }
+
+    /// Returns the name.
+    pub fn name(&self) -> &XorName {
+        self.address.name()
+    }
+
+    /// Returns size of contained value.
+    pub fn payload_size(&self) -> usize {
+        self.value.len()
+    }
+
+    /// Returns size of this data after serialisation.
+    pub fn serialised_size(&self) -> u64 {
+        serialized_size(self).unwrap_or(u64::MAX)
+    }
+
+    /// Returns true if the size is valid.
+    pub fn validate_size(&self) -> bool {
+        self.serialised_size() <= MAX_BLOB_SIZE_IN_BYTES && validate_metadata_size(&self.metadata)
+    }
+}
+
+/// Serialised as `(codec, value, metadata)`, where `value` is encoded according to `codec`.
+/// This replaces the bare `value` layout of earlier versions, which no longer deserialises.
+impl Serialize for PublicData {
+    fn serialize<S: Serializer>(&self, serialiser: S) -> Result<S::Ok, S::Error> {
+        (
+            self.codec(),
+            encoded(&self.value, &self.compressed),
+            &self.metadata,
+        )
+            .serialize(serialiser)
+    }
+}
+
+impl<'de> Deserialize<'de> for PublicData {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let (codec, encoded, metadata): (Codec, Vec<u8>, Option<Metadata>) =
+            Deserialize::deserialize(deserializer)?;
+        let (value, compressed) = compression::decode(codec, encoded).map_err(de::Error::custom)?;
+        Ok(PublicData::from_parts(
+            value.into(),
+            compressed.map(Bytes::from),
+            metadata,
+        ))
+    }
+}
+
+impl Debug for PublicData {
+    fn fmt(&self, formatter: &mut Formatter) -> fmt::Result {
+        write!(formatter, "PublicBloblob {:?}", self.name())
+    }
+}
+
+// Blobs are identified by their address and value alone, so that
+// the same content compares equal however it is stored.
+macro_rules! impl_identity {
+    ($flavour:ident) => {
+        impl PartialEq for $flavour {
+            fn eq(&self, other: &Self) -> bool {
+                self.address == other.address && self.value == other.value
+            }
+        }
+
+        impl Eq for $flavour {}
+
+        impl Hash for $flavour {
+            fn hash<H: Hasher>(&self, state: &mut H) {
+                self.address.hash(state);
+                self.value.hash(state);
+            }
+        }
+
+        impl PartialOrd for $flavour {
+            fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+                Some(self.cmp(other))
+            }
+        }
+
+        impl Ord for $flavour {
+            fn cmp(&self, other: &Self) -> Ordering {
+                (&self.address, &self.value).cmp(&(&other.address, &other.value))
+            }
+        }
+    };
+}
+
+impl_identity!(PrivateData);
+impl_identity!(PublicData);
+
+/// Kind of an Blob.
+#[derive(Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Hash, Serialize, Deserialize, Debug)]
+pub enum Kind {
+    /// Private.
+    Private,
+    /// Public.
+    Pub,
+}
+
+impl Kind {
+    /// Creates `Kind` from a `published` flag.
+    pub fn from_flag(published: bool) -> Self {
+        if published {
+            Kind::Pub
+        } else {
+            Kind::Private
+        }
+    }
+
+    /// Returns true if published.
+    pub fn is_pub(self) -> bool {
+        self == Kind::Pub
+    }
+
+    /// Returns true if unpublished.
+    pub fn is_unpub(self) -> bool {
+        !self.is_pub()
+    }
+}
+
+/// Address of an Blob.
+#[derive(Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Hash, Serialize, Deserialize, Debug)]
+pub enum Address {
+    /// Private namespace.
+    Private(XorName),
+    /// Public namespace.
+    Public(XorName),
+}
+
+impl Address {
+    /// Constructs an `Address` given `kind` and `name`.
+    pub fn from_kind(kind: Kind, name: XorName) -> Self {
+        match kind {
+            Kind::Pub => Address::Public(name),
+            Kind::Private => Address::Private(name),
+        }
+    }
+
+    /// Computes the address of the Blob with the content of `reader`, without holding the
+    /// content in memory. The address is that of a private Blob owned by `owner` if one is given,
+    /// and of a public Blob otherwise.
+    ///
+    /// Fails with `io::ErrorKind::InvalidData` if the content is larger than
+    /// `MAX_BLOB_SIZE_IN_BYTES`.
+    pub fn from_reader<R: Read>(reader: R, owner: Option<PublicKey>) -> io::Result<Self> {
+        let hash_of_value = hash_reader(reader, |_| ())?;
+        Ok(match owner {
+            Some(owner) => Self::private(hash_of_value, &owner, None),
+            None => Self::public(hash_of_value, None),
+        })
+    }
+
+    fn private(hash_of_value: [u8; 32], owner: &PublicKey, metadata: Option<&Metadata>) -> Self {
+        let serialised_contents = match metadata {
+            Some(metadata) => utils::serialise(&(hash_of_value, owner, metadata)),
+            None => utils::serialise(&(hash_of_value, owner)),
+        };
+        Address::Private(XorName(tiny_keccak::sha3_256(&serialised_contents)))
+    }
+
+    fn public(hash_of_value: [u8; 32], metadata: Option<&Metadata>) -> Self {
+        let name = match metadata {
+            Some(metadata) => tiny_keccak::sha3_256(&utils::serialise(&(hash_of_value, metadata))),
+            None => hash_of_value,
+        };
+        Address::Public(XorName(name))
+    }
+
+    /// Returns the kind.
+    pub fn kind(&self) -> Kind {
+        match self {
+            Address::Private(_) => Kind::Private,
+            Address::Public(_) => Kind::Pub,
+        }
+    }
+
+    /// Returns the name.
+    pub fn name(&self) -> &XorName {
+        match self {
+            Address::Private(ref name) | Address::Public(ref name) => name,
+        }
+    }
+
+    /// Returns true if published.
+    pub fn is_pub(&self) -> bool {
+        self.kind().is_pub()
+    }
+
+    /// Returns true if unpublished.
+    pub fn is_unpub(&self) -> bool {
+        self.kind().is_unpub()
+    }
+
+    /// Returns the Address serialised and encoded in z-base-32.
+    pub fn encode_to_zbase32(&self) -> String {
+        utils::encode(&self)
+    }
+
+    /// Creates from z-base-32 encoded string.
+    pub fn decode_from_zbase32<T: Decodable>(encoded: T) -> Result<Self, Error> {
+        utils::decode(encoded)
+    }
+}
+
+/// Object storing an Blob variant.
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Serialize, Deserialize, Debug)]
+pub enum Data {
+    /// Private Blob.
+    Private(PrivateData),
+    /// Public Blob.
+    Public(PublicData),
+}
+
+impl Data {
+    /// Returns the address.
+    pub fn address(&self) -> &Address {
+        match self {
+            Data::Private(data) => data.address(),
+            Data::Public(data) => data.address(),
+        }
+    }
+
+    /// Returns the name.
+    pub fn name(&self) -> &XorName {
+        self.address().name()
+    }
+
+    /// Returns the kind.
+    pub fn kind(&self) -> Kind {
+        self.address().kind()
+    }
+
+    /// Returns true if published.
+    pub fn is_pub(&self) -> bool {
+        self.kind().is_pub()
+    }
+
+    /// Returns true if unpublished.
+    pub fn is_unpub(&self) -> bool {
+        self.kind().is_unpub()
+    }
+
+    /// Returns the value, decompressed if stored compressed.
+    /// The value is reference counted, so cloning it is cheap.
+    pub fn value(&self) -> &Bytes {
+        match self {
+            Data::Private(data) => data.value(),
+            Data::Public(data) => data.value(),
+        }
+    }
+
+    /// Returns the encoding the value is stored with.
+    pub fn codec(&self) -> Codec {
+        match self {
+            Data::Private(data) => data.codec(),
+            Data::Public(data) => data.codec(),
+        }
+    }
+
+    /// Returns the metadata.
+    pub fn metadata(&self) -> Option<&Metadata> {
+        match self {
+            Data::Private(data) => data.metadata(),
+            Data::Public(data) => data.metadata(),
+        }
+    }
+
+    /// Returns `len` bytes of the value, starting at `offset`.
+    ///
+    /// Returns `Err(Error::InvalidRange)` if the range is not within the value.
+    pub fn range(&self, offset: u64, len: u64) -> Result<&[u8], Error> {
+        let value = self.value();
+        let size = value.len() as u64;
+        match offset.checked_add(len) {
+            Some(end) if end <= size => Ok(&value[offset as usize..end as usize]),
+            _ => Err(Error::InvalidRange(size)),
+        }
+    }
+
+    /// Returns the proof that this chunk is held, in response to the challenge `nonce`:
+    /// the hash of `nonce` followed by the value.
+    pub fn storage_proof(&self, nonce: &[u8; 32]) -> [u8; 32] {
+        let mut hasher = tiny_keccak::Keccak::new_sha3_256();
+        hasher.update(nonce);
+        hasher.update(self.value());
+        let mut proof = [0; 32];
+        hasher.finalize(&mut proof);
+        proof
+    }
+
+    /// Returns `Ok(())` if `proof` is the response to the challenge `nonce` from a holder of
+    /// this chunk, and `Err(Error::InvalidStorageProof)` otherwise.
+    pub fn verify_storage_proof(&self, nonce: &[u8; 32], proof: &[u8; 32]) -> Result<(), Error> {
+        if self.storage_proof(nonce) == *proof {
+            Ok(())
+        } else {
+            Err(Error::InvalidStorageProof)
+        }
+    }
+
+    /// Returns `true` if the size is valid.
+    pub fn validate_size(&self) -> bool {
+        match self {
+            Data::Private(data) => data.validate_size(),
+            Data::Public(data) => data.validate_size(),
+        }
+    }
+
+    /// Returns size of this data after serialisation.
+    pub fn serialised_size(&self) -> u64 {
+        match self {
+            Data::Private(data) => data.serialised_size(),
+            Data::Public(data) => data.serialised_size(),
+        }
+    }
+}
+
+fn codec(compressed: &Option<Bytes>) -> Codec {
+    if compressed.is_some() {
+        Codec::Deflate
+    } else {
+        Codec::Raw
+    }
+}
+
+// Reads `reader` to the end, passing the content read to `sink` as it goes,
+// and returns the hash of the content. Fails once more than `MAX_BLOB_SIZE_IN_BYTES`
+// have been read, as the content would not fit in a Blob.
+fn hash_reader<R: Read>(mut reader: R, mut sink: impl FnMut(&[u8])) -> io::Result<[u8; 32]> {
+    let mut hasher = tiny_keccak::Keccak::new_sha3_256();
+    let mut buffer = [0; 64 * 1024];
+    let mut size = 0;
+    loop {
+        match reader.read(&mut buffer) {
+            Ok(0) => break,
+            Ok(len) => {
+                size += len as u64;
+                if size > MAX_BLOB_SIZE_IN_BYTES {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        "content exceeds the maximum Blob size",
+                    ));
+                }
+                hasher.update(&buffer[..len]);
+                sink(&buffer[..len]);
+            }
+            Err(ref error) if error.kind() == io::ErrorKind::Interrupted => continue,
+            Err(error) => return Err(error),
+        }
+    }
+    let mut hash = [0; 32];
+    hasher.finalize(&mut hash);
+    Ok(hash)
+}
+
+fn validate_metadata_size(metadata: &Option<Metadata>) -> bool {
+    metadata.as_ref().map_or(true, Metadata::validate_size)
+}
+
+// The bytes a value is serialised as.
+fn encoded<'a>(value: &'a [u8], compressed: &'a Option<Bytes>) -> &'a [u8] {
+    match compressed {
+        Some(compressed) => &compressed[..],
+        None => value,
+    }
+}
+
+impl From<PrivateData> for Data {
+    fn from(data: PrivateData) -> Self {
+        Data::Private(data)
+    }
+}
+
+impl From<PublicData> for Data {
+    fn from(data: PublicData) -> Self {
+        Data::Public(data)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        utils, Address, Codec, Data, Error, Metadata, PrivateData, PublicData, PublicKey, XorName,
+        MAX_BLOB_SIZE_IN_BYTES, MAX_METADATA_SIZE_IN_BYTES,
+    };
+    use bincode::deserialize as deserialise;
+    use hex::encode;
+    use rand::{self, Rng, SeedableRng};
+    use rand_xorshift::XorShiftRng;
+    use std::{
+        env,
+        io::{self, Read},
+        iter, thread,
+    };
+    use threshold_crypto::SecretKey;
+    use unwrap::{unwrap, unwrap_err};
+
+    #[test]
+    fn deterministic_name() {
+        let data1 = b"Hello".to_vec();
+        let data2 = b"Goodbye".to_vec();
+
+        let owner1 = PublicKey::Bls(SecretKey::random().public_key());
+        let owner2 = PublicKey::Bls(SecretKey::random().public_key());
+
+        let idata1 = PrivateData::new(data1.clone(), owner1);
+        let idata2 = PrivateData::new(data1, owner2);
+        let idata3 = PrivateData::new(data2.clone(), owner1);
+        let idata3_clone = PrivateData::new(data2, owner1);
+
+        assert_eq!(idata3, idata3_clone);
+
+        assert_ne!(idata1.name(), idata2.name());
+        assert_ne!(idata1.name(), idata3.name());
+        assert_ne!(idata2.name(), idata3.name());
+    }
+
+    #[test]
+    fn deterministic_test() {
+        let value = "immutable data value".to_owned().into_bytes();
+        let blob = PublicData::new(value);
+        let blob_name = encode(blob.name().0.as_ref());
+        let expected_name = "fac2869677ee06277633c37ac7e8e5c655f3d652f707c7a79fab930d584a3016";
+
+        assert_eq!(&expected_name, &blob_name);
+    }
+
+    #[test]
+    fn serialisation() {
+        let mut rng = get_rng();
+        let len = rng.gen_range(1, 10_000);
+        let value = iter::repeat_with(|| rng.gen()).take(len).collect();
+        let blob = PublicData::new(value);
+        let serialised = utils::serialise(&blob);
+        let parsed = unwrap!(deserialise(&serialised));
+        assert_eq!(blob, parsed);
+    }
+
+    #[test]
+    fn compressed_serialisation() {
+        let value: Vec<u8> = iter::repeat(&b"{\"key\": \"value\"}, "[..])
+            .take(MAX_BLOB_SIZE_IN_BYTES as usize / 8)
+            .flatten()
+            .cloned()
+            .collect();
+        let owner = PublicKey::Bls(SecretKey::random().public_key());
+
+        let blob = PublicData::new_compressed(value.clone());
+        assert_eq!(blob.codec(), Codec::Deflate);
+        assert_eq!(blob.address(), PublicData::new(value.clone()).address());
+        assert_eq!(blob, PublicData::new(value.clone()));
+        assert!(blob.validate_size());
+        assert!(!PublicData::new(value.clone()).validate_size());
+        let parsed: PublicData = unwrap!(deserialise(&utils::serialise(&blob)));
+        assert_eq!(parsed, blob);
+        assert_eq!(parsed.value(), &value);
+
+        let blob = PrivateData::new_compressed(value.clone(), owner);
+        assert_eq!(
+            blob.address(),
+            PrivateData::new(value.clone(), owner).address()
+        );
+        assert_eq!(blob, PrivateData::new(value.clone(), owner));
+        let parsed: PrivateData = unwrap!(deserialise(&utils::serialise(&blob)));
+        assert_eq!(parsed, blob);
+        assert_eq!(parsed.value(), &value);
+    }
+
+    #[test]
+    fn incompressible_value_is_stored_raw() {
+        let mut rng = get_rng();
+        let value: Vec<u8> = iter::repeat_with(|| rng.gen()).take(10_000).collect();
+
+        let blob = PublicData::new_compressed(value.clone());
+        assert_eq!(blob.codec(), Codec::Raw);
+        assert_eq!(
+            blob.serialised_size(),
+            PublicData::new(value.clone()).serialised_size()
+        );
+        let parsed: PublicData = unwrap!(deserialise(&utils::serialise(&blob)));
+        assert_eq!(parsed.value(), &value);
+    }
+
+    #[test]
+    fn clones_share_the_value() {
+        let blob = PublicData::new(b"shared value".to_vec());
+        let clone = blob.clone();
+        assert_eq!(blob.value().as_ptr(), clone.value().as_ptr());
+
+        // The value is serialised as a plain byte vector would be.
+        assert_eq!(
+            utils::serialise(&blob),
+            utils::serialise(&(Codec::Raw, b"shared value".to_vec(), None::<Metadata>))
+        );
+    }
+
+    #[test]
+    fn from_reader() {
+        let mut rng = get_rng();
+        let value: Vec<u8> = iter::repeat_with(|| rng.gen()).take(200_000).collect();
+        let owner = PublicKey::Bls(SecretKey::random().public_key());
+
+        let blob = PublicData::new(value.clone());
+        assert_eq!(unwrap!(PublicData::from_reader(&value[..])), blob);
+        assert_eq!(
+            unwrap!(Address::from_reader(&value[..], None)),
+            *blob.address()
+        );
+
+        let blob = PrivateData::new(value.clone(), owner);
+        assert_eq!(unwrap!(PrivateData::from_reader(&value[..], owner)), blob);
+        assert_eq!(
+            unwrap!(Address::from_reader(&value[..], Some(owner))),
+            *blob.address()
+        );
+
+        let too_large = io::repeat(0).take(MAX_BLOB_SIZE_IN_BYTES + 1);
+        let error = unwrap_err!(PublicData::from_reader(too_large));
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn storage_proof() {
+        let blob: Data = PublicData::new(b"chunk".to_vec()).into();
+        let nonce = rand::random();
+        let proof = blob.storage_proof(&nonce);
+        assert_eq!(blob.verify_storage_proof(&nonce, &proof), Ok(()));
+        assert_eq!(
+            blob.verify_storage_proof(&rand::random(), &proof),
+            Err(Error::InvalidStorageProof)
+        );
+        let other: Data = PublicData::new(b"other chunk".to_vec()).into();
+        assert_eq!(
+            other.verify_storage_proof(&nonce, &proof),
+            Err(Error::InvalidStorageProof)
+        );
+    }
+
+    #[test]
+    fn metadata() {
+        let value = b"<html></html>".to_vec();
+        let mut metadata = Metadata {
+            content_type: Some("text/html".to_string()),
+            file_name: Some("index.html".to_string()),
+            created: Some(1_600_000_000),
+            ..Default::default()
+        };
+        let _ = metadata
+            .attributes
+            .insert("lang".to_string(), "en".to_string());
+
+        let blob = PublicData::new(value.clone()).with_metadata(metadata.clone());
+        assert_eq!(blob.metadata(), Some(&metadata));
+        assert_ne!(blob.address(), PublicData::new(value.clone()).address());
+        let parsed: PublicData = unwrap!(deserialise(&utils::serialise(&blob)));
+        assert_eq!(parsed, blob);
+
+        let owner = PublicKey::Bls(SecretKey::random().public_key());
+        let blob = PrivateData::new(value.clone(), owner).with_metadata(metadata.clone());
+        assert_ne!(
+            blob.address(),
+            PrivateData::new(value.clone(), owner).address()
+        );
+        let parsed: PrivateData = unwrap!(deserialise(&utils::serialise(&blob)));
+        assert_eq!(parsed, blob);
+
+        let _ = metadata.attributes.insert(
+            "padding".to_string(),
+            "x".repeat(MAX_METADATA_SIZE_IN_BYTES as usize),
+        );
+        assert!(!PublicData::new(value)
+            .with_metadata(metadata)
+            .validate_size());
+    }
+
+    fn get_rng() -> XorShiftRng {
+        let env_var_name = "RANDOM_SEED";
+        let seed = env::var(env_var_name)
+            .ok()
+            .map(|value| {
+                unwrap!(
+                    value.parse::<u64>(),
+                    "Env var 'RANDOM_SEED={}' is not a valid u64.",
+                    value
+                )
+            })
+            .unwrap_or_else(rand::random);
+        println!(
+            "To replay this '{}', set env var {}={}",
+            unwrap!(thread::current().name()),
+            env_var_name,
+            seed
+        );
+        XorShiftRng::seed_from_u64(seed)
+    }
+
+    #[test]
+    fn zbase32_encode_decode_idata_address() {
+        let name = XorName(rand::random());
+        let address = Address::Public(name);
+        let encoded = address.encode_to_zbase32();
+        let decoded = unwrap!(self::Address::decode_from_zbase32(&encoded));
+        assert_eq!(address, decoded);
+    }
+}
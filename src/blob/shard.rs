@@ -0,0 +1,243 @@
+// Copyright 2020 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under the MIT license <LICENSE-MIT
+// https://opensource.org/licenses/MIT> or the Modified BSD license <LICENSE-BSD
+// https://opensource.org/licenses/BSD-3-Clause>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms. Please review the Licences for the
+// specific language governing permissions and limitations relating to use of the SAFE Network
+// Software.
+
+use super::{Address, Data};
+use crate::{utils, Error, Result, XorName};
+use reed_solomon_erasure::galois_8::ReedSolomon;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Address of a shard of an erasure coded Blob.
+#[derive(Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Hash, Serialize, Deserialize, Debug)]
+pub struct ShardAddress {
+    /// Address of the Blob.
+    pub blob: Address,
+    /// Index of the shard among all the shards of the Blob.
+    pub index: u8,
+}
+
+impl ShardAddress {
+    /// Returns the name, used to select the holders of the shard.
+    pub fn name(&self) -> XorName {
+        XorName(tiny_keccak::sha3_256(&utils::serialise(self)))
+    }
+}
+
+/// A shard of an erasure coded Blob.
+///
+/// A Blob is split into `total` shards, any `required` of which are enough to reconstruct it.
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Serialize, Deserialize, Debug)]
+pub struct Shard {
+    /// Network address.
+    address: ShardAddress,
+    /// Number of shards needed to reconstruct the Blob.
+    required: u8,
+    /// Total number of shards of the Blob.
+    total: u8,
+    /// Size of the serialised Blob.
+    blob_size: u64,
+    /// Contained data.
+    value: Vec<u8>,
+    /// Hash of the value, to detect corrupted shards.
+    hash: [u8; 32],
+}
+
+impl Shard {
+    /// Splits the serialised `blob` into `total` shards, any `required` of which
+    /// are enough to reconstruct it.
+    ///
+    /// Returns `Err(Error::InvalidOperation)` unless `0 < required < total`.
+    pub fn split(blob: &Data, required: u8, total: u8) -> Result<Vec<Self>> {
+        let coder = coder(required, total)?;
+
+        let serialised = utils::serialise(blob);
+        let shard_size = (serialised.len() + required as usize - 1) / required as usize;
+        let mut values: Vec<Vec<u8>> = serialised
+            .chunks(shard_size)
+            .map(|chunk| {
+                let mut value = chunk.to_vec();
+                value.resize(shard_size, 0);
+                value
+            })
+            .collect();
+        values.resize(total as usize, vec![0; shard_size]);
+        coder
+            .encode(&mut values)
+            .map_err(|_| Error::InvalidOperation)?;
+
+        let shards = values
+            .into_iter()
+            .enumerate()
+            .map(|(index, value)| Self {
+                address: ShardAddress {
+                    blob: *blob.address(),
+                    index: index as u8,
+                },
+                required,
+                total,
+                blob_size: serialised.len() as u64,
+                hash: tiny_keccak::sha3_256(&value),
+                value,
+            })
+            .collect();
+        Ok(shards)
+    }
+
+    /// Reconstructs a Blob from its shards, which can be in any order. Corrupted shards and
+    /// shards of other Blobs are ignored.
+    ///
+    /// Returns `Err(Error::NoSuchData)` if fewer than the required number of intact shards are
+    /// given, and `Err(Error::InvalidChunk)` if the shards do not reconstruct the Blob they are of.
+    pub fn reconstruct(shards: &[Self]) -> Result<Data> {
+        let mut groups: BTreeMap<_, BTreeMap<_, _>> = BTreeMap::new();
+        for shard in shards
+            .iter()
+            .filter(|shard| shard.is_intact() && shard.address.index < shard.total)
+        {
+            let _ = groups
+                .entry(shard.split_key())
+                .or_default()
+                .insert(shard.address.index, shard);
+        }
+
+        let mut result = Err(Error::NoSuchData);
+        for group in groups.values() {
+            let first = match group.values().next() {
+                Some(first) if group.len() >= first.required as usize => *first,
+                _ => continue,
+            };
+            result = Self::reconstruct_group(first, group);
+            if result.is_ok() {
+                break;
+            }
+        }
+        result
+    }
+
+    // Reconstructs a Blob from intact shards of it, all split the same way as `first`.
+    fn reconstruct_group(first: &Self, shards: &BTreeMap<u8, &Self>) -> Result<Data> {
+        let shard_size = first.value.len();
+        let mut values: Vec<Option<Vec<u8>>> = (0..first.total)
+            .map(|index| match shards.get(&index) {
+                Some(shard) if shard.value.len() == shard_size => Some(shard.value.clone()),
+                _ => None,
+            })
+            .collect();
+        coder(first.required, first.total)?
+            .reconstruct_data(&mut values)
+            .map_err(|_| Error::InvalidChunk)?;
+
+        let mut serialised: Vec<u8> = values
+            .into_iter()
+            .take(first.required as usize)
+            .flatten()
+            .flatten()
+            .collect();
+        serialised.truncate(first.blob_size as usize);
+        let blob: Data = bincode::deserialize(&serialised).map_err(|_| Error::InvalidChunk)?;
+        if *blob.address() != first.address.blob {
+            return Err(Error::InvalidChunk);
+        }
+        Ok(blob)
+    }
+
+    /// Returns the address.
+    pub fn address(&self) -> &ShardAddress {
+        &self.address
+    }
+
+    /// Returns the number of shards needed to reconstruct the Blob.
+    pub fn required(&self) -> u8 {
+        self.required
+    }
+
+    /// Returns the total number of shards of the Blob.
+    pub fn total(&self) -> u8 {
+        self.total
+    }
+
+    /// Returns the value.
+    pub fn value(&self) -> &Vec<u8> {
+        &self.value
+    }
+
+    /// Returns true if the value matches the hash it was split with.
+    pub fn is_intact(&self) -> bool {
+        tiny_keccak::sha3_256(&self.value) == self.hash
+    }
+
+    // Returns what identifies the shards of the same Blob, split the same way.
+    fn split_key(&self) -> (Address, u8, u8, u64) {
+        (self.address.blob, self.required, self.total, self.blob_size)
+    }
+}
+
+fn coder(required: u8, total: u8) -> Result<ReedSolomon> {
+    if required == 0 || required >= total {
+        return Err(Error::InvalidOperation);
+    }
+    ReedSolomon::new(required as usize, (total - required) as usize)
+        .map_err(|_| Error::InvalidOperation)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Shard;
+    use crate::{Error, PublicBlob};
+    use unwrap::unwrap;
+
+    #[test]
+    fn split_and_reconstruct() {
+        let blob = PublicBlob::new(b"some content to split into shards".to_vec()).into();
+        let shards = unwrap!(Shard::split(&blob, 3, 5));
+        assert_eq!(shards.len(), 5);
+
+        assert_eq!(unwrap!(Shard::reconstruct(&shards[2..])), blob);
+        assert_eq!(unwrap!(Shard::reconstruct(&shards[..3])), blob);
+        let some: Vec<_> = vec![shards[4].clone(), shards[0].clone(), shards[2].clone()];
+        assert_eq!(unwrap!(Shard::reconstruct(&some)), blob);
+
+        assert_eq!(Shard::reconstruct(&shards[3..]), Err(Error::NoSuchData));
+        assert_eq!(Shard::split(&blob, 3, 3), Err(Error::InvalidOperation));
+    }
+
+    #[test]
+    fn reconstruct_skips_corrupted_shards() {
+        let blob = PublicBlob::new(b"some content to split into shards".to_vec()).into();
+        let mut shards = unwrap!(Shard::split(&blob, 2, 4));
+        shards[0].value[0] ^= 1;
+        assert!(!shards[0].is_intact());
+        assert_eq!(unwrap!(Shard::reconstruct(&shards)), blob);
+        assert_eq!(Shard::reconstruct(&shards[..2]), Err(Error::NoSuchData));
+
+        // A shard altered along with its hash is not detected until reconstruction.
+        shards[0].hash = tiny_keccak::sha3_256(&shards[0].value);
+        assert_eq!(Shard::reconstruct(&shards[..2]), Err(Error::InvalidChunk));
+    }
+
+    #[test]
+    fn reconstruct_skips_shards_of_other_blobs() {
+        let blob = PublicBlob::new(b"some content to split into shards".to_vec()).into();
+        let other = PublicBlob::new(b"some other content".to_vec()).into();
+        let shards = unwrap!(Shard::split(&blob, 2, 4));
+        let other_shards = unwrap!(Shard::split(&other, 2, 4));
+
+        let mixed = vec![
+            other_shards[0].clone(),
+            shards[1].clone(),
+            shards[3].clone(),
+        ];
+        assert_eq!(unwrap!(Shard::reconstruct(&mixed)), blob);
+
+        // Shards of the same Blob split another way are not mixed in either.
+        let resplit = unwrap!(Shard::split(&blob, 3, 4));
+        let mixed = vec![resplit[0].clone(), resplit[1].clone(), shards[2].clone()];
+        assert_eq!(Shard::reconstruct(&mixed), Err(Error::NoSuchData));
+    }
+}
@@ -0,0 +1,476 @@
+// Copyright 2020 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under the MIT license <LICENSE-MIT
+// https://opensource.org/licenses/MIT> or the Modified BSD license <LICENSE-BSD
+// https://opensource.org/licenses/BSD-3-Clause>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms. Please review the Licences for the
+// specific language governing permissions and limitations relating to use of the SAFE Network
+// Software.
+
+//! Map data type as a CRDT.
+//!
+//! Keys are observed-remove: a key removed concurrently with a write to it is kept. Each key
+//! holds a multi-value register, so concurrent writes to the same key are all kept until a
+//! later write, which has seen them, replaces them.
+
+use crate::{
+    utils, Error, MapAction as Action, MapLimits as Limits, MapPermissionSet as PermissionSet,
+    PublicKey, Result,
+};
+use bincode::serialized_size;
+use crdts::{map, CmRDT, MVReg};
+use serde::{Deserialize, Serialize};
+use std::{
+    cmp::Ordering,
+    collections::{BTreeMap, BTreeSet},
+    fmt::{self, Debug, Formatter},
+    hash::{Hash, Hasher},
+};
+use xor_name::XorName;
+
+// Type of data used for the 'Actor' in CRDT vector clocks
+type ActorType = PublicKey;
+
+/// Register holding the concurrently written values of a key.
+type Register = MVReg<Vec<u8>, ActorType>;
+
+/// CRDT operation on the entries of a Map.
+pub type Op = map::Op<Vec<u8>, Register, ActorType>;
+
+/// Entries of a CRDT Map, with all the concurrently written values of each key.
+pub type Entries = BTreeMap<Vec<u8>, Values>;
+
+/// Concurrently written values of a key, in ascending order.
+pub type Values = Vec<Vec<u8>>;
+
+/// Address of a CRDT Map.
+#[derive(Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Hash, Serialize, Deserialize, Debug)]
+pub struct Address {
+    name: XorName,
+    tag: u64,
+}
+
+impl Address {
+    /// Constructs a new `Address` given `name` and `tag`.
+    pub fn new(name: XorName, tag: u64) -> Self {
+        Self { name, tag }
+    }
+
+    /// Returns the name.
+    pub fn name(&self) -> &XorName {
+        &self.name
+    }
+
+    /// Returns the tag.
+    pub fn tag(&self) -> u64 {
+        self.tag
+    }
+}
+
+/// Write operation to apply to a CRDT Map.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct WriteOp {
+    /// Address of a CRDT Map on the network.
+    pub address: Address,
+    /// The operation to apply.
+    pub crdt_op: Op,
+}
+
+impl Debug for WriteOp {
+    fn fmt(&self, formatter: &mut Formatter) -> fmt::Result {
+        write!(formatter, "CrdtMapWriteOp {:?}", self.address.name())
+    }
+}
+
+// CRDT operations are compared by their serialised form, as they only contain ordered
+// collections.
+impl PartialEq for WriteOp {
+    fn eq(&self, other: &Self) -> bool {
+        utils::serialise(self) == utils::serialise(other)
+    }
+}
+
+impl Eq for WriteOp {}
+
+impl Hash for WriteOp {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        utils::serialise(self).hash(state)
+    }
+}
+
+impl Ord for WriteOp {
+    fn cmp(&self, other: &Self) -> Ordering {
+        utils::serialise(self).cmp(&utils::serialise(other))
+    }
+}
+
+impl PartialOrd for WriteOp {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Map data type as a CRDT.
+///
+/// Write operations on the entries can be applied in any order. The owner, permissions and size
+/// limits are set when the Map is created.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Data {
+    /// Address on the network of this piece of data.
+    address: Address,
+    /// CRDT to store the entries.
+    data: map::Map<Vec<u8>, Register, ActorType>,
+    /// Maps an application key to a list of allowed or forbidden actions.
+    permissions: BTreeMap<PublicKey, PermissionSet>,
+    /// Contains the public key of an owner or owners of this data.
+    owner: PublicKey,
+    /// Size limits of the Map.
+    limits: Limits,
+}
+
+impl Data {
+    /// Constructs a new empty CRDT Map.
+    pub fn new(name: XorName, tag: u64, owner: PublicKey) -> Self {
+        Self::new_with_permissions(name, tag, Default::default(), owner)
+    }
+
+    /// Constructs a new empty CRDT Map with the given permissions.
+    pub fn new_with_permissions(
+        name: XorName,
+        tag: u64,
+        permissions: BTreeMap<PublicKey, PermissionSet>,
+        owner: PublicKey,
+    ) -> Self {
+        Self {
+            address: Address::new(name, tag),
+            data: map::Map::new(),
+            permissions,
+            owner,
+            limits: Default::default(),
+        }
+    }
+
+    /// Sets the size limits, which can only be lowered from the network maximums.
+    ///
+    /// Returns `Err(TooManyEntries)` or `Err(ExceededSize)` if the Map exceeds the limits.
+    pub fn with_limits(mut self, limits: Limits) -> Result<Self> {
+        self.limits = limits;
+        self.check_size()?;
+        Ok(self)
+    }
+
+    /// Returns the size limits.
+    pub fn limits(&self) -> &Limits {
+        &self.limits
+    }
+
+    /// Returns size of this data after serialisation.
+    pub fn serialised_size(&self) -> u64 {
+        serialized_size(self).unwrap_or(u64::MAX)
+    }
+
+    /// Checks that the Map is within its size limits, and that the limits are within the
+    /// network maximums. All the concurrently written values of a key count towards the size.
+    ///
+    /// Returns `Err(TooManyEntries)` if there are too many entries, and
+    /// `Err(ExceededSize)` if any other limit is exceeded.
+    pub fn check_size(&self) -> Result<()> {
+        self.limits.check()?;
+        if self.len() as u64 > self.limits.max_entries {
+            return Err(Error::TooManyEntries);
+        }
+        let oversized = self.entries().iter().any(|(key, values)| {
+            key.len() as u64 > self.limits.max_key_size
+                || values
+                    .iter()
+                    .any(|value| value.len() as u64 > self.limits.max_value_size)
+        });
+        if oversized || self.serialised_size() > self.limits.max_size {
+            return Err(Error::ExceededSize);
+        }
+        Ok(())
+    }
+
+    /// Returns the address.
+    pub fn address(&self) -> &Address {
+        &self.address
+    }
+
+    /// Returns the name.
+    pub fn name(&self) -> &XorName {
+        self.address.name()
+    }
+
+    /// Returns the tag.
+    pub fn tag(&self) -> u64 {
+        self.address.tag()
+    }
+
+    /// Returns the owner key.
+    pub fn owner(&self) -> &PublicKey {
+        &self.owner
+    }
+
+    /// Returns all the permissions.
+    pub fn permissions(&self) -> &BTreeMap<PublicKey, PermissionSet> {
+        &self.permissions
+    }
+
+    /// Returns the permissions of the provided user.
+    pub fn user_permissions(&self, user: &PublicKey) -> Result<&PermissionSet> {
+        self.permissions.get(user).ok_or(Error::NoSuchKey)
+    }
+
+    /// Checks if the provided user is the owner or is allowed to perform `action`.
+    pub fn check_permission(&self, action: Action, requester: &PublicKey) -> Result<()> {
+        if *requester == self.owner {
+            return Ok(());
+        }
+        match self.permissions.get(requester) {
+            Some(permissions) if permissions.is_allowed(action) => Ok(()),
+            _ => Err(Error::AccessDenied),
+        }
+    }
+
+    /// Returns the number of keys.
+    pub fn len(&self) -> usize {
+        self.data.len().val
+    }
+
+    /// Returns `true` if there are no keys.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the concurrently written values of `key`, if it exists.
+    ///
+    /// The values are sorted, as the register holds them in the order they were applied, which
+    /// differs between replicas.
+    pub fn get(&self, key: &[u8]) -> Option<Values> {
+        self.data.get(&key.to_vec()).val.map(|register| {
+            let mut values = register.read().val;
+            values.sort();
+            values
+        })
+    }
+
+    /// Returns all the keys.
+    pub fn keys(&self) -> BTreeSet<Vec<u8>> {
+        self.data.keys().map(|key| key.val.clone()).collect()
+    }
+
+    /// Returns all the entries.
+    pub fn entries(&self) -> Entries {
+        self.keys()
+            .into_iter()
+            .filter_map(|key| {
+                let values = self.get(&key)?;
+                Some((key, values))
+            })
+            .collect()
+    }
+
+    /// Writes `value` at `key`, replacing the values `actor` has seen.
+    ///
+    /// Returns the operation, to be applied on the other replicas.
+    pub fn write(&mut self, key: Vec<u8>, value: Vec<u8>, actor: PublicKey) -> Result<WriteOp> {
+        let action = if self.data.get(&key).val.is_some() {
+            Action::Update
+        } else {
+            Action::Insert
+        };
+        self.check_permission(action, &actor)?;
+
+        let ctx = self.data.get(&key).derive_add_ctx(actor);
+        let crdt_op = self
+            .data
+            .update(key, ctx, |register, ctx| register.write(value, ctx));
+        self.apply_checked(crdt_op.clone())?;
+
+        Ok(WriteOp {
+            address: self.address,
+            crdt_op,
+        })
+    }
+
+    /// Removes `key` and the values `actor` has seen.
+    ///
+    /// Returns the operation, to be applied on the other replicas.
+    pub fn remove(&mut self, key: Vec<u8>, actor: PublicKey) -> Result<WriteOp> {
+        self.check_permission(Action::Delete, &actor)?;
+
+        let read_ctx = self.data.get(&key);
+        if read_ctx.val.is_none() {
+            return Err(Error::NoSuchEntry);
+        }
+        let crdt_op = self.data.rm(key, read_ctx.derive_rm_ctx());
+        self.apply_checked(crdt_op.clone())?;
+
+        Ok(WriteOp {
+            address: self.address,
+            crdt_op,
+        })
+    }
+
+    /// Applies a write operation from another replica.
+    ///
+    /// Operations can be applied in any order, and applying one more than once has no effect.
+    /// Returns `Err(TooManyEntries)` or `Err(ExceededSize)`, leaving the Map unchanged, if the
+    /// operation would make it exceed its limits.
+    pub fn apply_op(&mut self, op: WriteOp) -> Result<()> {
+        if op.address != self.address {
+            return Err(Error::InvalidOperation);
+        }
+        self.apply_checked(op.crdt_op)
+    }
+
+    // Applies `crdt_op` if the Map stays within its limits.
+    fn apply_checked(&mut self, crdt_op: Op) -> Result<()> {
+        let mut updated = self.clone();
+        updated.data.apply(crdt_op);
+        updated.check_size()?;
+        *self = updated;
+        Ok(())
+    }
+
+    // The observable state, used for comparisons as the CRDT itself has no deterministic
+    // ordering.
+    fn state(&self) -> Vec<u8> {
+        utils::serialise(&(
+            &self.address,
+            &self.owner,
+            &self.permissions,
+            &self.limits,
+            self.entries(),
+        ))
+    }
+}
+
+impl Debug for Data {
+    fn fmt(&self, formatter: &mut Formatter) -> fmt::Result {
+        write!(formatter, "CrdtMap {:?}", self.name())
+    }
+}
+
+impl PartialEq for Data {
+    fn eq(&self, other: &Self) -> bool {
+        self.state() == other.state()
+    }
+}
+
+impl Eq for Data {}
+
+impl Hash for Data {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.state().hash(state)
+    }
+}
+
+impl Ord for Data {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.state().cmp(&other.state())
+    }
+}
+
+impl PartialOrd for Data {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Data, Error, Limits, PublicKey, XorName};
+    use crate::{MapAction as Action, MapPermissionSet as PermissionSet};
+    use threshold_crypto::SecretKey;
+    use unwrap::unwrap;
+
+    fn gen_public_key() -> PublicKey {
+        PublicKey::Bls(SecretKey::random().public_key())
+    }
+
+    #[test]
+    fn concurrent_writes_converge() {
+        let owner = gen_public_key();
+        let writer = gen_public_key();
+        let permissions = PermissionSet::new()
+            .allow(Action::Insert)
+            .allow(Action::Update)
+            .allow(Action::Delete);
+        let mut replica1 = Data::new_with_permissions(
+            XorName(rand::random()),
+            15000,
+            vec![(writer, permissions)].into_iter().collect(),
+            owner,
+        );
+        let mut replica2 = replica1.clone();
+
+        let op1 = unwrap!(replica1.write(b"key".to_vec(), b"value1".to_vec(), owner));
+        let op2 = unwrap!(replica2.write(b"key".to_vec(), b"value2".to_vec(), writer));
+        let op3 = unwrap!(replica2.write(b"other".to_vec(), b"value3".to_vec(), writer));
+        let op4 = unwrap!(replica2.remove(b"other".to_vec(), writer));
+
+        // Apply the operations in a different order on each replica, some of them twice.
+        unwrap!(replica1.apply_op(op4.clone()));
+        unwrap!(replica1.apply_op(op2.clone()));
+        unwrap!(replica1.apply_op(op3));
+        unwrap!(replica1.apply_op(op2));
+        unwrap!(replica2.apply_op(op1));
+        unwrap!(replica2.apply_op(op4));
+
+        assert_eq!(replica1, replica2);
+        assert_eq!(replica1.keys().len(), 1);
+        assert_eq!(
+            replica1.get(b"key"),
+            Some(vec![b"value1".to_vec(), b"value2".to_vec()])
+        );
+        assert_eq!(replica1.entries(), replica2.entries());
+
+        // A write which has seen both values replaces them.
+        let op = unwrap!(replica1.write(b"key".to_vec(), b"value4".to_vec(), owner));
+        unwrap!(replica2.apply_op(op));
+        assert_eq!(replica2.get(b"key"), Some(vec![b"value4".to_vec()]));
+    }
+
+    #[test]
+    fn write_requires_permission() {
+        let mut data = Data::new(XorName(rand::random()), 15000, gen_public_key());
+        match data.write(b"key".to_vec(), b"value".to_vec(), gen_public_key()) {
+            Err(Error::AccessDenied) => (),
+            result => panic!("Unexpected result: {:?}", result),
+        }
+        assert!(data.is_empty());
+    }
+
+    #[test]
+    fn size_limits() {
+        let owner = gen_public_key();
+        let limits = Limits {
+            max_entries: 1,
+            max_value_size: 4,
+            ..Default::default()
+        };
+        let mut data =
+            unwrap!(Data::new(XorName(rand::random()), 15000, owner).with_limits(limits));
+        let mut replica = data.clone();
+
+        match data.write(b"key".to_vec(), b"value".to_vec(), owner) {
+            Err(Error::ExceededSize) => (),
+            result => panic!("Unexpected result: {:?}", result),
+        }
+        assert!(data.is_empty());
+        let _ = unwrap!(data.write(b"key".to_vec(), b"val".to_vec(), owner));
+        match data.write(b"other".to_vec(), b"val".to_vec(), owner) {
+            Err(Error::TooManyEntries) => (),
+            result => panic!("Unexpected result: {:?}", result),
+        }
+
+        // Operations from other replicas are checked too.
+        let op = unwrap!(replica.write(b"other".to_vec(), b"val".to_vec(), owner));
+        match data.apply_op(op) {
+            Err(Error::TooManyEntries) => (),
+            result => panic!("Unexpected result: {:?}", result),
+        }
+        assert_eq!(data.keys().len(), 1);
+    }
+}
@@ -97,6 +97,17 @@ pub enum Error {
     BalanceExists,
     /// Expected data size exceeded.
     ExceededSize,
+    /// A chunk does not match its data map, or encrypted content cannot be encrypted or
+    /// decrypted with the given key.
+    InvalidChunk,
+    /// Requested range is out of the bounds of the data. Contains the size of the data.
+    InvalidRange(u64),
+    /// A proof of storage does not match the chunk it is for.
+    InvalidStorageProof,
+    /// The section does not have enough space left to store the data.
+    NotEnoughSpace,
+    /// The section is not known.
+    NoSuchSection,
 }
 
 impl<T: Into<String>> From<T> for Error {
@@ -156,6 +167,20 @@ impl Display for Error {
             Error::BalanceExists => write!(f, "Balance already exists"),
             Error::DuplicateMessageId => write!(f, "MessageId already exists"),
             Error::ExceededSize => write!(f, "Size of the structure exceeds the limit"),
+            Error::InvalidChunk => write!(
+                f,
+                "Chunk does not match its data map, or content does not match its key"
+            ),
+            Error::InvalidStorageProof => write!(f, "Proof of storage does not match the chunk"),
+            Error::InvalidRange(size) => {
+                write!(
+                    f,
+                    "Requested range is out of bounds of data of size {}",
+                    size
+                )
+            }
+            Error::NotEnoughSpace => write!(f, "Not enough space in the section to store the data"),
+            Error::NoSuchSection => write!(f, "Section is not known"),
         }
     }
 }
@@ -194,6 +219,11 @@ impl error::Error for Error {
             Error::BalanceExists => "Balance already exists",
             Error::DuplicateMessageId => "MessageId already exists",
             Error::ExceededSize => "Exceeded the size limit",
+            Error::InvalidChunk => "Invalid chunk",
+            Error::InvalidRange(_) => "Invalid range",
+            Error::InvalidStorageProof => "Invalid storage proof",
+            Error::NotEnoughSpace => "Not enough space",
+            Error::NoSuchSection => "No such section",
         }
     }
 }
@@ -207,4 +237,6 @@ pub enum EntryError {
     EntryExists(u8),
     /// Invalid version when updating an entry. Contains the current entry Key.
     InvalidSuccessor(u8),
+    /// Entry value does not match the expected one.
+    ValueMismatch,
 }
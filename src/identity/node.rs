@@ -8,7 +8,7 @@
 // Software.
 
 use crate::keys::{BlsKeypairShare, SignatureShare};
-use crate::{utils, Error, PublicKey, Signature};
+use crate::{utils, Ed25519Proof, Error, PublicKey, Signature};
 use ed25519_dalek::{Keypair as Ed25519Keypair, PublicKey as Ed25519PublicKey};
 use hex_fmt::HexFmt;
 use multibase::Decodable;
@@ -280,6 +280,14 @@ impl NodeKeypairs {
         Signature::Ed25519(self.ed25519.sign(data.as_ref()))
     }
 
+    /// Creates an Ed25519 proof of `data`, i.e. the public key together with the signature.
+    pub fn ed25519_proof<T: AsRef<[u8]>>(&self, data: T) -> Ed25519Proof {
+        Ed25519Proof {
+            public_key: self.ed25519.public,
+            signature: self.ed25519.sign(data.as_ref()),
+        }
+    }
+
     /// Creates a detached BLS signature share of `data` if the `self` holds a BLS keypair share.
     pub fn sign_using_bls<T: AsRef<[u8]>>(&self, data: T) -> Option<Signature> {
         self.bls.as_ref().map(|keys| {
@@ -186,7 +186,7 @@ impl Debug for BlsProofShare {
 }
 
 /// A value together with the proof that it was agreed on by the quorum of the section elders.
-#[derive(Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[derive(Clone, Hash, PartialEq, Eq, Debug, Serialize, Deserialize)]
 pub struct Proven<T> {
     ///
     pub value: T,
@@ -200,3 +200,10 @@ impl<T> Proven<T> {
         Self { value, proof }
     }
 }
+
+impl<T: Serialize> Proven<T> {
+    /// Verifies the proof against the serialised value.
+    pub fn verify(&self) -> bool {
+        self.proof.verify(&utils::serialise(&self.value))
+    }
+}
@@ -29,10 +29,12 @@
 )]
 
 mod blob;
+mod crdt_map;
 mod errors;
 mod identity;
 mod keys;
 mod map;
+mod map_encryption;
 mod messaging;
 mod money;
 mod rewards;
@@ -41,8 +43,16 @@ mod transfer;
 mod utils;
 
 pub use blob::{
-    Address as BlobAddress, Data as Blob, Kind as BlobKind, PrivateData as PrivateBlob,
-    PublicData as PublicBlob, MAX_BLOB_SIZE_IN_BYTES,
+    Address as BlobAddress, ChunkInfo as BlobChunkInfo, Codec as BlobCodec, Data as Blob,
+    DataMap as BlobDataMap, DataMapLevel as BlobDataMapLevel, Encryption as BlobEncryption,
+    Kind as BlobKind, MerkleProof as BlobMerkleProof, MerkleTree as BlobMerkleTree,
+    Metadata as BlobMetadata, PrivateData as PrivateBlob, PublicData as PublicBlob,
+    Shard as BlobShard, ShardAddress as BlobShardAddress, MAX_BLOB_SIZE_IN_BYTES,
+    MAX_CHUNK_SIZE_IN_BYTES, MAX_DECOMPRESSED_BLOB_SIZE_IN_BYTES, MAX_METADATA_SIZE_IN_BYTES,
+};
+pub use crdt_map::{
+    Address as CrdtMapAddress, Data as CrdtMap, Entries as CrdtMapEntries, Op as CrdtMapOp,
+    Values as CrdtMapValues, WriteOp as CrdtMapWriteOp,
 };
 pub use errors::{EntryError, Error, Result};
 pub use identity::{
@@ -56,16 +66,25 @@ pub use keys::{
     PublicKey, Signature, SignatureShare,
 };
 pub use map::{
-    Action as MapAction, Address as MapAddress, Data as Map, Entries as MapEntries,
-    EntryActions as MapEntryActions, Kind as MapKind, PermissionSet as MapPermissionSet,
-    SeqData as SeqMap, SeqEntries as MapSeqEntries, SeqEntryAction as MapSeqEntryAction,
-    SeqEntryActions as MapSeqEntryActions, SeqValue as MapSeqValue, UnseqData as UnseqMap,
-    UnseqEntries as MapUnseqEntries, UnseqEntryAction as MapUnseqEntryAction,
-    UnseqEntryActions as MapUnseqEntryActions, Value as MapValue, Values as MapValues,
+    Action as MapAction, Address as MapAddress, Data as Map, Delta as MapDelta,
+    Entries as MapEntries, EntriesPage as MapEntriesPage, EntryActions as MapEntryActions,
+    Guard as MapGuard, KeyRange as MapKeyRange, KeysPage as MapKeysPage, Kind as MapKind,
+    Limits as MapLimits, OwnerOp as MapOwnerOp, Owners as MapOwners, Page as MapPage,
+    PermissionSet as MapPermissionSet, SeqData as SeqMap, SeqDelta as MapSeqDelta,
+    SeqEntries as MapSeqEntries, SeqEntryAction as MapSeqEntryAction,
+    SeqEntryActions as MapSeqEntryActions, SeqValue as MapSeqValue,
+    SignedOwnerOp as MapSignedOwnerOp, Transaction as MapTransaction, UnseqData as UnseqMap,
+    UnseqDelta as MapUnseqDelta, UnseqEntries as MapUnseqEntries,
+    UnseqEntryAction as MapUnseqEntryAction, UnseqEntryActions as MapUnseqEntryActions,
+    Value as MapValue, Values as MapValues, MAX_MAP_ENTRIES, MAX_MAP_KEY_SIZE_IN_BYTES,
+    MAX_MAP_SIZE_IN_BYTES, MAX_MAP_VALUE_SIZE_IN_BYTES,
 };
+pub use map_encryption::EntryCipher as MapEntryCipher;
 pub use messaging::*;
 pub use money::Money;
-pub use rewards::{RewardCounter, Work};
+pub use rewards::{
+    PayoutFailure, PayoutStage, RelocatedRewards, RewardCounter, RewardPayout, RewardPayouts, Work,
+};
 
 pub use sequence::{
     Action as SequenceAction, Address as SequenceAddress, Data as Sequence,
@@ -96,6 +115,8 @@ pub enum Data {
     Mutable(Map),
     /// Sequence.
     Sequence(Sequence),
+    /// Map as a CRDT.
+    CrdtMap(CrdtMap),
 }
 
 impl Data {
@@ -103,8 +124,9 @@ impl Data {
     pub fn is_pub(&self) -> bool {
         match *self {
             Self::Immutable(ref idata) => idata.is_pub(),
-            Self::Mutable(_) => false,
+            Self::Mutable(ref map) => map.is_pub(),
             Self::Sequence(ref sequence) => sequence.is_pub(),
+            Self::CrdtMap(_) => false,
         }
     }
 
@@ -132,6 +154,12 @@ impl From<Sequence> for Data {
     }
 }
 
+impl From<CrdtMap> for Data {
+    fn from(data: CrdtMap) -> Self {
+        Self::CrdtMap(data)
+    }
+}
+
 /// Permissions for an app stored by the Client Handlers.
 #[derive(
     Copy, Hash, Eq, PartialEq, PartialOrd, Ord, Clone, Serialize, Deserialize, Default, Debug,
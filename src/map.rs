@@ -9,7 +9,8 @@
 
 //! Map
 //!
-//! All Map is unpublished. Map can be either sequenced or unsequenced.
+//! Map can be either public or private, and either sequenced or unsequenced. Anyone can read a
+//! public Map, and it cannot be deleted.
 //!
 //! ## Private data
 //!
@@ -27,19 +28,22 @@
 //! does not have to pass version numbers for keys, but it still must pass the next version number
 //! while modifying the Map shell.
 
-use crate::{utils, EntryError, Error, PublicKey, Result};
+use crate::{utils, EntryError, Error, PublicKey, Result, Signature};
+use bincode::serialized_size;
+use bytes::Bytes;
 use hex_fmt::HexFmt;
 use multibase::Decodable;
-use serde::{Deserialize, Serialize};
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
 use std::{
     collections::{btree_map::Entry, BTreeMap, BTreeSet},
     fmt::{self, Debug, Formatter},
     mem,
+    ops::Bound,
 };
 use xor_name::XorName;
 
-/// Map that is unpublished on the network. This data can only be fetched by the owner or
-/// those in the permissions fields with `Permission::Read` access.
+/// Sequenced Map. Unless it is public, this data can only be fetched by the owner or those in
+/// the permissions fields with `Permission::Read` access.
 #[derive(Hash, Eq, PartialEq, PartialOrd, Ord, Clone, Serialize, Deserialize)]
 pub struct SeqData {
     /// Network address.
@@ -48,12 +52,24 @@ pub struct SeqData {
     data: SeqEntries,
     /// Maps an application key to a list of allowed or forbidden actions.
     permissions: BTreeMap<PublicKey, PermissionSet>,
+    /// Actions allowed to anyone without their own permissions. Only used by public Maps.
+    anyone: PermissionSet,
     /// Version should be increased for any changes to Map fields except for data.
     version: u64,
+    /// Revision, increased for any change to the Map, including to its data.
+    revision: u64,
     /// Contains the public key of an owner or owners of this data.
     ///
     /// Data Handlers in vaults enforce that a mutation request has a valid signature of the owner.
     owner: PublicKey,
+    /// All the owners, including `owner`, and how many of them must sign owner-level operations.
+    owners: Owners,
+    /// Size limits of the Map.
+    limits: Limits,
+    /// Prior values of the keys.
+    history: History,
+    /// Recent changes, to compute the changes since a recent revision.
+    changes: ChangeLog<SeqValue>,
 }
 
 impl Debug for SeqData {
@@ -62,8 +78,8 @@ impl Debug for SeqData {
     }
 }
 
-/// Map that is unpublished on the network. This data can only be fetched by the owner or
-/// those in the permissions fields with `Permission::Read` access.
+/// Unsequenced Map. Unless it is public, this data can only be fetched by the owner or those in
+/// the permissions fields with `Permission::Read` access.
 #[derive(Hash, Eq, PartialEq, PartialOrd, Ord, Clone, Serialize, Deserialize)]
 pub struct UnseqData {
     /// Network address.
@@ -72,12 +88,22 @@ pub struct UnseqData {
     data: UnseqEntries,
     /// Maps an application key to a list of allowed or forbidden actions.
     permissions: BTreeMap<PublicKey, PermissionSet>,
+    /// Actions allowed to anyone without their own permissions. Only used by public Maps.
+    anyone: PermissionSet,
     /// Version should be increased for any changes to Map fields except for data.
     version: u64,
+    /// Revision, increased for any change to the Map, including to its data.
+    revision: u64,
     /// Contains the public key of an owner or owners of this data.
     ///
     /// Data Handlers in vaults enforce that a mutation request has a valid signature of the owner.
     owner: PublicKey,
+    /// All the owners, including `owner`, and how many of them must sign owner-level operations.
+    owners: Owners,
+    /// Size limits of the Map.
+    limits: Limits,
+    /// Recent changes, to compute the changes since a recent revision.
+    changes: ChangeLog<Bytes>,
 }
 
 impl Debug for UnseqData {
@@ -89,8 +115,8 @@ impl Debug for UnseqData {
 /// A value in sequenced Map.
 #[derive(Hash, Eq, PartialEq, PartialOrd, Ord, Clone, Serialize, Deserialize)]
 pub struct SeqValue {
-    /// Actual data.
-    pub data: Vec<u8>,
+    /// Actual data. It is reference counted, so cloning it is cheap.
+    pub data: Bytes,
     /// Version, incremented sequentially for any change to `data`.
     pub version: u64,
 }
@@ -107,7 +133,7 @@ pub enum Value {
     /// Sequenced value.
     Seq(SeqValue),
     /// Unsequenced value.
-    Unseq(Vec<u8>),
+    Unseq(Bytes),
 }
 
 impl From<SeqValue> for Value {
@@ -118,6 +144,12 @@ impl From<SeqValue> for Value {
 
 impl From<Vec<u8>> for Value {
     fn from(value: Vec<u8>) -> Self {
+        Value::Unseq(value.into())
+    }
+}
+
+impl From<Bytes> for Value {
+    fn from(value: Bytes) -> Self {
         Value::Unseq(value)
     }
 }
@@ -128,7 +160,7 @@ pub enum Values {
     /// List of sequenced values.
     Seq(Vec<SeqValue>),
     /// List of unsequenced values.
-    Unseq(Vec<Vec<u8>>),
+    Unseq(Vec<Bytes>),
 }
 
 impl From<Vec<SeqValue>> for Values {
@@ -139,6 +171,12 @@ impl From<Vec<SeqValue>> for Values {
 
 impl From<Vec<Vec<u8>>> for Values {
     fn from(values: Vec<Vec<u8>>) -> Self {
+        Values::Unseq(values.into_iter().map(Bytes::from).collect())
+    }
+}
+
+impl From<Vec<Bytes>> for Values {
+    fn from(values: Vec<Bytes>) -> Self {
         Values::Unseq(values)
     }
 }
@@ -191,7 +229,7 @@ pub enum Action {
 }
 
 macro_rules! impl_map {
-    ($flavour:ident) => {
+    ($flavour:ident, $value:ty) => {
         impl $flavour {
             /// Returns the address.
             pub fn address(&self) -> &Address {
@@ -208,6 +246,64 @@ macro_rules! impl_map {
                 self.address.tag()
             }
 
+            /// Returns the size limits.
+            pub fn limits(&self) -> &Limits {
+                &self.limits
+            }
+
+            /// Sets the size limits, which can only be lowered from the network maximums.
+            ///
+            /// Returns `Err(TooManyEntries)` or `Err(ExceededSize)` if the Map exceeds the limits.
+            pub fn with_limits(mut self, limits: Limits) -> Result<Self> {
+                self.limits = limits;
+                self.check_size()?;
+                Ok(self)
+            }
+
+            /// Enables keeping up to `limit` recent changes, so that the changes since a recent
+            /// revision can be computed with `changes_since`. The logged changes count towards
+            /// the size of the Map, and the oldest ones are dropped to keep it within its limit.
+            pub fn with_change_log(mut self, limit: u64) -> Self {
+                self.changes.prune(limit);
+                self
+            }
+
+            /// Returns the maximum number of recent changes kept. The change log is disabled if
+            /// zero.
+            pub fn change_log_limit(&self) -> u64 {
+                self.changes.limit
+            }
+
+            /// Returns size of this data after serialisation.
+            pub fn serialised_size(&self) -> u64 {
+                serialized_size(self).unwrap_or(u64::MAX)
+            }
+
+            /// Returns `true` if the size is valid.
+            pub fn validate_size(&self) -> bool {
+                self.check_size().is_ok()
+            }
+
+            /// Checks that the Map is within its size limits, and that the limits are within the
+            /// network maximums.
+            ///
+            /// Returns `Err(TooManyEntries)` if there are too many entries, and
+            /// `Err(ExceededSize)` if any other limit is exceeded.
+            pub fn check_size(&self) -> Result<()> {
+                self.limits.check()?;
+                if self.data.len() as u64 > self.limits.max_entries {
+                    return Err(Error::TooManyEntries);
+                }
+                let oversized = self.data.iter().any(|(key, value)| {
+                    key.len() as u64 > self.limits.max_key_size
+                        || value.value_size() as u64 > self.limits.max_value_size
+                });
+                if oversized || self.serialised_size() > self.limits.max_size {
+                    return Err(Error::ExceededSize);
+                }
+                Ok(())
+            }
+
             /// Returns the kind.
             pub fn kind(&self) -> Kind {
                 self.address.kind()
@@ -218,6 +314,12 @@ macro_rules! impl_map {
                 self.version
             }
 
+            /// Returns the revision, which any change to the Map increases, including changes
+            /// to its entries.
+            pub fn revision(&self) -> u64 {
+                self.revision
+            }
+
             /// Returns the owner key.
             pub fn owner(&self) -> &PublicKey {
                 &self.owner
@@ -228,14 +330,17 @@ macro_rules! impl_map {
                 self.data.keys().cloned().collect()
             }
 
-            /// Returns the shell of this Map (the fields without the data).
-            pub fn shell(&self) -> Self {
-                Self {
-                    address: self.address.clone(),
-                    data: BTreeMap::new(),
-                    permissions: self.permissions.clone(),
-                    version: self.version,
-                    owner: self.owner,
+            /// Returns up to `limit` keys in `range`, starting from `continue_from` if given.
+            pub fn keys_in_range(
+                &self,
+                range: &KeyRange,
+                limit: u64,
+                continue_from: Option<&[u8]>,
+            ) -> KeysPage {
+                let page = range_page(&self.data, range, limit, continue_from);
+                Page {
+                    items: page.items.into_iter().map(|(key, _)| key).collect(),
+                    continue_from: page.continue_from,
                 }
             }
 
@@ -249,52 +354,131 @@ macro_rules! impl_map {
                 self.permissions.get(&user).ok_or(Error::NoSuchKey)
             }
 
-            /// Checks if the provided user is an owner.
+            /// Returns all the owners.
+            pub fn owners(&self) -> &Owners {
+                &self.owners
+            }
+
+            /// Returns `true` if the provided user is one of the owners.
+            pub fn is_owner(&self, user: &PublicKey) -> bool {
+                self.owners.contains(user)
+            }
+
+            /// Checks if the provided user can perform owner-level operations alone, i.e. is an
+            /// owner of a Map with a threshold of one. Otherwise, owner-level operations need
+            /// the signatures of enough owners and are applied with `apply_owner_op`.
             ///
             /// Returns `Ok(())` on success and `Err(Error::AccessDenied)` if the user is not an
-            /// owner.
+            /// owner or cannot act alone.
             pub fn check_is_owner(&self, requester: PublicKey) -> Result<()> {
-                if self.owner == requester {
+                if self.is_owner(&requester) && self.owners.threshold == 1 {
                     Ok(())
                 } else {
                     Err(Error::AccessDenied)
                 }
             }
 
+            /// Applies an owner-level operation signed by at least the threshold of owners.
+            ///
+            /// Returns `Err(AccessDenied)` if there are not enough valid owner signatures, and
+            /// `Err(InvalidOperation)` if the operation is for another Map, or is a conversion,
+            /// which gives a Map of another type and is applied with `Data::apply_owner_op`.
+            pub fn apply_owner_op(&mut self, op: SignedOwnerOp) -> Result<()> {
+                if op.address != self.address {
+                    return Err(Error::InvalidOperation);
+                }
+                if op.valid_signers(&self.owners) < self.owners.threshold {
+                    return Err(Error::AccessDenied);
+                }
+                match op.op {
+                    OwnerOp::ChangeOwners { owner, owners } => {
+                        if !owners.contains(&owner) {
+                            return Err(Error::InvalidOwners);
+                        }
+                        if op.version != self.version + 1 {
+                            return Err(Error::InvalidSuccessor(self.version));
+                        }
+                        let change = self.fields_change();
+                        let old_owner = mem::replace(&mut self.owner, owner);
+                        let old_owners = mem::replace(&mut self.owners, owners);
+                        if let Err(error) = self.commit(change) {
+                            self.owner = old_owner;
+                            self.owners = old_owners;
+                            return Err(error);
+                        }
+                        self.version = op.version;
+                        self.revision += 1;
+                        Ok(())
+                    }
+                    OwnerOp::SetUserPermissions { user, permissions } => {
+                        self.apply_user_permissions(user, permissions, op.version)
+                    }
+                    OwnerOp::DelUserPermissions { user } => {
+                        self.apply_del_user_permissions(user, op.version)
+                    }
+                    OwnerOp::SetAnyonePermissions { permissions } => {
+                        self.apply_anyone_permissions(permissions, op.version)
+                    }
+                    OwnerOp::Convert => Err(Error::InvalidOperation),
+                }
+            }
+
             /// Checks permissions for given `action` for the provided user.
             ///
+            /// If owner-level operations need more than one owner, permissions can only be
+            /// managed with `apply_owner_op`, so `ManagePermissions` is never allowed.
+            ///
             /// Returns `Err(Error::AccessDenied)` if the permission check has failed.
             pub fn check_permissions(&self, action: Action, requester: PublicKey) -> Result<()> {
-                if self.owner == requester {
+                if action == Action::ManagePermissions && self.owners.threshold > 1 {
+                    return self.check_is_owner(requester);
+                }
+                if self.is_owner(&requester) || self.is_action_allowed(&requester, action) {
                     Ok(())
                 } else {
-                    let permissions = self
-                        .user_permissions(requester)
-                        .map_err(|_| Error::AccessDenied)?;
-                    if permissions.is_allowed(action) {
-                        Ok(())
-                    } else {
-                        Err(Error::AccessDenied)
-                    }
+                    Err(Error::AccessDenied)
                 }
             }
 
             /// Inserts or updates permissions for the provided user.
             ///
             /// Requires the new `version` of the Map fields. If it does not match the
-            /// current version + 1, an error will be returned.
+            /// current version + 1, an error will be returned. Returns `Err(InvalidOwners)` if
+            /// owner-level operations need more than one owner, in which case permissions are
+            /// set with `apply_owner_op`.
             pub fn set_user_permissions(
                 &mut self,
                 user: PublicKey,
                 permissions: PermissionSet,
                 version: u64,
+            ) -> Result<()> {
+                if self.owners.threshold != 1 {
+                    return Err(Error::InvalidOwners);
+                }
+                self.apply_user_permissions(user, permissions, version)
+            }
+
+            fn apply_user_permissions(
+                &mut self,
+                user: PublicKey,
+                permissions: PermissionSet,
+                version: u64,
             ) -> Result<()> {
                 if version != self.version + 1 {
                     return Err(Error::InvalidSuccessor(self.version));
                 }
 
-                let _prev = self.permissions.insert(user, permissions);
+                let change = self.fields_change();
+                let prev = self.permissions.insert(user, permissions);
+                if let Err(error) = self.commit(change) {
+                    let _ = match prev {
+                        Some(prev) => self.permissions.insert(user, prev),
+                        None => self.permissions.remove(&user),
+                    };
+                    return Err(error);
+                }
                 self.version = version;
+                self.revision += 1;
 
                 Ok(())
             }
@@ -302,8 +486,17 @@ macro_rules! impl_map {
             /// Deletes permissions for the provided user.
             ///
             /// Requires the new `version` of the Map fields. If it does not match the
-            /// current version + 1, an error will be returned.
+            /// current version + 1, an error will be returned. Returns `Err(InvalidOwners)` if
+            /// owner-level operations need more than one owner, in which case permissions are
+            /// deleted with `apply_owner_op`.
             pub fn del_user_permissions(&mut self, user: PublicKey, version: u64) -> Result<()> {
+                if self.owners.threshold != 1 {
+                    return Err(Error::InvalidOwners);
+                }
+                self.apply_del_user_permissions(user, version)
+            }
+
+            fn apply_del_user_permissions(&mut self, user: PublicKey, version: u64) -> Result<()> {
                 if version != self.version + 1 {
                     return Err(Error::InvalidSuccessor(self.version));
                 }
@@ -311,8 +504,16 @@ macro_rules! impl_map {
                     return Err(Error::NoSuchKey);
                 }
 
-                let _ = self.permissions.remove(&user);
+                let change = self.fields_change();
+                let prev = self.permissions.remove(&user);
+                if let Err(error) = self.commit(change) {
+                    if let Some(prev) = prev {
+                        let _ = self.permissions.insert(user, prev);
+                    }
+                    return Err(error);
+                }
                 self.version = version;
+                self.revision += 1;
 
                 Ok(())
             }
@@ -330,23 +531,38 @@ macro_rules! impl_map {
                     return false;
                 }
 
+                self.changes.record(self.fields_change());
                 let _ = self.permissions.remove(&user);
                 self.version = version;
+                self.revision += 1;
 
                 true
             }
 
-            /// Changes the owner.
+            /// Changes the owner, making it the only one.
             ///
             /// Requires the new `version` of the Map fields. If it does not match the
-            /// current version + 1, an error will be returned.
+            /// current version + 1, an error will be returned. Returns `Err(InvalidOwners)` if
+            /// owner-level operations need more than one owner, in which case the owners are
+            /// changed with `apply_owner_op`.
             pub fn change_owner(&mut self, new_owner: PublicKey, version: u64) -> Result<()> {
+                if self.owners.threshold != 1 {
+                    return Err(Error::InvalidOwners);
+                }
                 if version != self.version + 1 {
                     return Err(Error::InvalidSuccessor(self.version));
                 }
 
-                self.owner = new_owner;
+                let change = self.fields_change();
+                let old_owner = mem::replace(&mut self.owner, new_owner);
+                let old_owners = mem::replace(&mut self.owners, Owners::single(new_owner));
+                if let Err(error) = self.commit(change) {
+                    self.owner = old_owner;
+                    self.owners = old_owners;
+                    return Err(error);
+                }
                 self.version = version;
+                self.revision += 1;
 
                 Ok(())
             }
@@ -354,7 +570,8 @@ macro_rules! impl_map {
             /// Changes the owner without performing any validation.
             ///
             /// Requires the new `version` of the Map fields. If it does not match the
-            /// current version + 1, an error will be returned.
+            /// current version + 1, an error will be returned. If owner-level operations need
+            /// more than one owner, the owners are kept and `new_owner` must be one of them.
             pub fn change_owner_without_validation(
                 &mut self,
                 new_owner: PublicKey,
@@ -363,26 +580,252 @@ macro_rules! impl_map {
                 if version <= self.version {
                     return false;
                 }
+                if self.owners.threshold > 1 && !self.owners.contains(&new_owner) {
+                    return false;
+                }
 
+                self.changes.record(self.fields_change());
                 self.owner = new_owner;
+                if self.owners.threshold == 1 {
+                    self.owners = Owners::single(new_owner);
+                }
                 self.version = version;
+                self.revision += 1;
 
                 true
             }
 
             /// Returns true if `action` is allowed for the provided user.
+            ///
+            /// Anyone can read a public Map. Users without their own permissions on a public Map
+            /// have the permissions set for anyone.
             pub fn is_action_allowed(&self, requester: &PublicKey, action: Action) -> bool {
+                if self.is_pub() && action == Action::Read {
+                    return true;
+                }
                 match self.permissions.get(requester) {
                     Some(perms) => perms.is_allowed(action),
-                    None => false,
+                    None => self.is_pub() && self.anyone.is_allowed(action),
+                }
+            }
+
+            /// Returns `true` if public.
+            pub fn is_pub(&self) -> bool {
+                self.address.is_pub()
+            }
+
+            /// Gets the permissions for anyone without their own permissions.
+            pub fn anyone_permissions(&self) -> &PermissionSet {
+                &self.anyone
+            }
+
+            /// Sets the permissions for anyone without their own permissions, e.g. to make a
+            /// public Map world-insertable.
+            ///
+            /// Requires the new `version` of the Map fields. If it does not match the
+            /// current version + 1, an error will be returned. Returns `Err(InvalidOperation)`
+            /// if the Map is not public, and `Err(InvalidOwners)` if owner-level operations
+            /// need more than one owner, in which case the permissions are set with
+            /// `apply_owner_op`.
+            pub fn set_anyone_permissions(
+                &mut self,
+                permissions: PermissionSet,
+                version: u64,
+            ) -> Result<()> {
+                if self.owners.threshold != 1 {
+                    return Err(Error::InvalidOwners);
+                }
+                self.apply_anyone_permissions(permissions, version)
+            }
+
+            fn apply_anyone_permissions(
+                &mut self,
+                permissions: PermissionSet,
+                version: u64,
+            ) -> Result<()> {
+                if !self.is_pub() {
+                    return Err(Error::InvalidOperation);
+                }
+                if version != self.version + 1 {
+                    return Err(Error::InvalidSuccessor(self.version));
+                }
+
+                let change = self.fields_change();
+                let old_anyone = mem::replace(&mut self.anyone, permissions);
+                if let Err(error) = self.commit(change) {
+                    self.anyone = old_anyone;
+                    return Err(error);
+                }
+                self.version = version;
+                self.revision += 1;
+
+                Ok(())
+            }
+
+            // Returns the change to log before changing the fields.
+            fn fields_change(&self) -> Change<$value> {
+                Change {
+                    revision: self.revision,
+                    entries: BTreeMap::new(),
+                    fields: if self.changes.limit == 0 {
+                        None
+                    } else {
+                        Some(Fields {
+                            permissions: self.permissions.clone(),
+                            anyone: self.anyone.clone(),
+                            version: self.version,
+                            owner: self.owner,
+                            owners: self.owners.clone(),
+                        })
+                    },
+                }
+            }
+
+            // Returns the change to log before changing the entries with `keys`.
+            fn entries_change<'a, K>(&self, keys: K) -> Change<$value>
+            where
+                K: IntoIterator<Item = &'a Vec<u8>>,
+            {
+                let entries = if self.changes.limit == 0 {
+                    BTreeMap::new()
+                } else {
+                    keys.into_iter()
+                        .map(|key| (key.clone(), self.data.get(key).cloned()))
+                        .collect()
+                };
+                Change {
+                    revision: self.revision,
+                    entries,
+                    fields: None,
+                }
+            }
+
+            // Returns this Map as it was at `revision`, by undoing the logged changes since.
+            //
+            // Returns `Err(InvalidSuccessor)` with the current revision if `revision` is newer,
+            // and `Err(NoSuchEntry)` if the changes since `revision` are no longer logged.
+            fn at_revision(&self, revision: u64) -> Result<Self> {
+                if revision > self.revision {
+                    return Err(Error::InvalidSuccessor(self.revision));
+                }
+                let mut older = self.clone();
+                for change in self.changes.changes.iter().rev() {
+                    if older.revision <= revision {
+                        break;
+                    }
+                    for (key, value) in &change.entries {
+                        let _ = match value {
+                            Some(value) => older.data.insert(key.clone(), value.clone()),
+                            None => older.data.remove(key),
+                        };
+                    }
+                    if let Some(fields) = &change.fields {
+                        older.permissions = fields.permissions.clone();
+                        older.anyone = fields.anyone.clone();
+                        older.version = fields.version;
+                        older.owner = fields.owner;
+                        older.owners = fields.owners.clone();
+                    }
+                    older.revision = change.revision;
+                }
+                if older.revision == revision {
+                    Ok(older)
+                } else {
+                    Err(Error::NoSuchEntry)
+                }
+            }
+
+            // Returns the delta from `self` to `newer` with the given entry changes.
+            fn delta<A>(&self, newer: &Self, entries: A) -> DeltaOf<A> {
+                let mut permissions: BTreeMap<_, _> = newer
+                    .permissions
+                    .iter()
+                    .filter(|(user, perms)| self.permissions.get(user) != Some(perms))
+                    .map(|(user, perms)| (*user, Some(perms.clone())))
+                    .collect();
+                permissions.extend(
+                    self.permissions
+                        .keys()
+                        .filter(|user| !newer.permissions.contains_key(user))
+                        .map(|user| (*user, None)),
+                );
+                let anyone = if newer.anyone != self.anyone {
+                    Some(newer.anyone.clone())
+                } else {
+                    None
+                };
+                let owner = if newer.owner != self.owner {
+                    Some(newer.owner)
+                } else {
+                    None
+                };
+                let owners = if newer.owners != self.owners {
+                    Some(newer.owners.clone())
+                } else {
+                    None
+                };
+                DeltaOf {
+                    entries,
+                    permissions,
+                    anyone,
+                    owner,
+                    owners,
+                    version: newer.version,
+                    revision: newer.revision,
+                    hash: newer.state_hash(),
+                }
+            }
+
+            // Hash of the Map fields and entries, which deltas are verified against.
+            fn state_hash(&self) -> [u8; 32] {
+                tiny_keccak::sha3_256(&utils::serialise(&(
+                    &self.address,
+                    &self.data,
+                    &self.permissions,
+                    &self.anyone,
+                    self.version,
+                    self.revision,
+                    &self.owner,
+                    &self.owners,
+                )))
+            }
+
+            // Applies the permission and owner changes of a delta, then checks that the result
+            // is the Map the delta was computed for.
+            fn apply_shell<A>(&mut self, delta: DeltaOf<A>) -> Result<()> {
+                if delta.version < self.version || delta.revision < self.revision {
+                    return Err(Error::InvalidSuccessor(self.version));
+                }
+                for (user, permissions) in delta.permissions {
+                    let _ = match permissions {
+                        Some(permissions) => self.permissions.insert(user, permissions),
+                        None => self.permissions.remove(&user),
+                    };
+                }
+                if let Some(anyone) = delta.anyone {
+                    self.anyone = anyone;
+                }
+                if let Some(owner) = delta.owner {
+                    self.owner = owner;
+                }
+                if let Some(owners) = delta.owners {
+                    self.owners = owners;
+                }
+                self.version = delta.version;
+                self.revision = delta.revision;
+
+                if self.state_hash() == delta.hash {
+                    Ok(())
+                } else {
+                    Err(Error::InvalidSuccessor(self.version))
                 }
             }
         }
     };
 }
 
-impl_map!(SeqData);
-impl_map!(UnseqData);
+impl_map!(SeqData, SeqValue);
+impl_map!(UnseqData, Bytes);
 
 impl UnseqData {
     /// Creates a new unsequenced Map.
@@ -391,48 +834,139 @@ impl UnseqData {
             address: Address::Unseq { name, tag },
             data: Default::default(),
             permissions: Default::default(),
+            anyone: Default::default(),
             version: 0,
+            revision: 0,
+            owners: Owners::single(owner),
             owner,
+            limits: Default::default(),
+            changes: Default::default(),
         }
     }
 
+    /// Creates a new public unsequenced Map, readable by anyone.
+    pub fn new_pub(name: XorName, tag: u64, owner: PublicKey) -> Self {
+        Self {
+            address: Address::PublicUnseq { name, tag },
+            ..Self::new(name, tag, owner)
+        }
+    }
+
+    /// Creates a new public unsequenced Map with entries and permissions, readable by anyone.
+    ///
+    /// Returns `Err(TooManyEntries)` or `Err(ExceededSize)` if the Map exceeds the default limits.
+    pub fn new_pub_with_data(
+        name: XorName,
+        tag: u64,
+        data: UnseqEntries,
+        permissions: BTreeMap<PublicKey, PermissionSet>,
+        owner: PublicKey,
+    ) -> Result<Self> {
+        Ok(Self {
+            address: Address::PublicUnseq { name, tag },
+            ..Self::new_with_data(name, tag, data, permissions, owner)?
+        })
+    }
+
     /// Creates a new unsequenced Map with entries and permissions.
+    ///
+    /// Returns `Err(TooManyEntries)` or `Err(ExceededSize)` if the Map exceeds the default limits.
     pub fn new_with_data(
         name: XorName,
         tag: u64,
         data: UnseqEntries,
         permissions: BTreeMap<PublicKey, PermissionSet>,
         owner: PublicKey,
-    ) -> Self {
-        Self {
+    ) -> Result<Self> {
+        let data = Self {
             address: Address::Unseq { name, tag },
             data,
             permissions,
+            anyone: Default::default(),
             version: 0,
+            revision: 0,
+            owners: Owners::single(owner),
             owner,
-        }
+            limits: Default::default(),
+            changes: Default::default(),
+        };
+        data.check_size()?;
+        Ok(data)
     }
 
     /// Returns a value for the given key.
-    pub fn get(&self, key: &[u8]) -> Option<&Vec<u8>> {
+    pub fn get(&self, key: &[u8]) -> Option<&Bytes> {
         self.data.get(key)
     }
 
     /// Returns values of all entries.
-    pub fn values(&self) -> Vec<Vec<u8>> {
+    pub fn values(&self) -> Vec<Bytes> {
         self.data.values().cloned().collect()
     }
 
+    /// Returns the shell of this Map (the fields without the data).
+    pub fn shell(&self) -> Self {
+        Self {
+            address: self.address.clone(),
+            data: BTreeMap::new(),
+            permissions: self.permissions.clone(),
+            anyone: self.anyone.clone(),
+            version: self.version,
+            revision: self.revision,
+            owner: self.owner,
+            owners: self.owners.clone(),
+            limits: self.limits,
+            changes: ChangeLog::new(self.changes.limit),
+        }
+    }
+
     /// Returns all entries.
     pub fn entries(&self) -> &UnseqEntries {
         &self.data
     }
 
+    /// Returns up to `limit` entries in `range`, starting from `continue_from` if given.
+    pub fn entries_in_range(
+        &self,
+        range: &KeyRange,
+        limit: u64,
+        continue_from: Option<&[u8]>,
+    ) -> Page<UnseqEntries> {
+        range_page(&self.data, range, limit, continue_from)
+    }
+
     /// Removes and returns all entries.
     pub fn take_entries(&mut self) -> UnseqEntries {
         mem::replace(&mut self.data, BTreeMap::new())
     }
 
+    /// Converts into a sequenced Map at the sequenced address with the same name and tag.
+    ///
+    /// Entries, permissions and owners are kept, and every entry is given version 0. Logged
+    /// changes are dropped. Returns `Err(TooManyEntries)` or `Err(ExceededSize)` if the
+    /// sequenced Map exceeds its limits.
+    pub fn into_seq(self) -> Result<SeqData> {
+        let data = SeqData {
+            address: self.address.with_kind(Kind::Seq),
+            data: self
+                .data
+                .into_iter()
+                .map(|(key, data)| (key, SeqValue { data, version: 0 }))
+                .collect(),
+            permissions: self.permissions,
+            anyone: self.anyone,
+            version: self.version,
+            revision: self.revision,
+            owner: self.owner,
+            owners: self.owners,
+            limits: self.limits,
+            history: Default::default(),
+            changes: ChangeLog::new(self.changes.limit),
+        };
+        data.check_size()?;
+        Ok(data)
+    }
+
     /// Mutates entries based on `actions` for the provided user.
     ///
     /// Returns `Err(InvalidEntryActions)` if the mutation parameters are invalid.
@@ -441,10 +975,11 @@ impl UnseqData {
         actions: UnseqEntryActions,
         requester: PublicKey,
     ) -> Result<()> {
+        let change = self.entries_change(actions.actions.keys());
         let (insert, update, delete) = actions.actions.into_iter().fold(
             (
-                BTreeMap::<Vec<u8>, Vec<u8>>::new(),
-                BTreeMap::<Vec<u8>, Vec<u8>>::new(),
+                BTreeMap::<Vec<u8>, Bytes>::new(),
+                BTreeMap::<Vec<u8>, Bytes>::new(),
                 BTreeSet::<Vec<u8>>::new(),
             ),
             |(mut insert, mut update, mut delete), (key, item)| {
@@ -463,7 +998,7 @@ impl UnseqData {
             },
         );
 
-        if *self.owner() != requester
+        if !self.is_owner(&requester)
             && ((!insert.is_empty() && !self.is_action_allowed(&requester, Action::Insert))
                 || (!update.is_empty() && !self.is_action_allowed(&requester, Action::Update))
                 || (!delete.is_empty() && !self.is_action_allowed(&requester, Action::Delete)))
@@ -511,8 +1046,105 @@ impl UnseqData {
             return Err(Error::InvalidEntryActions(errors));
         }
 
-        let _old_data = mem::replace(&mut self.data, new_data);
+        let old_data = mem::replace(&mut self.data, new_data);
+        if let Err(error) = self.commit(change) {
+            self.data = old_data;
+            return Err(error);
+        }
+        self.revision += 1;
+
+        Ok(())
+    }
+
+    /// Returns the changes from this Map to `newer`, a later state of the same Map.
+    ///
+    /// Returns `Err(InvalidOperation)` if `newer` has a different address.
+    pub fn diff(&self, newer: &Self) -> Result<UnseqDelta> {
+        if newer.address != self.address {
+            return Err(Error::InvalidOperation);
+        }
+        let mut entries = UnseqEntryActions::new();
+        for (key, value) in &newer.data {
+            match self.data.get(key) {
+                None => entries.add_action(key.clone(), UnseqEntryAction::Ins(value.clone())),
+                Some(old_value) if old_value != value => {
+                    entries.add_action(key.clone(), UnseqEntryAction::Update(value.clone()))
+                }
+                Some(_) => (),
+            }
+        }
+        for key in self.data.keys() {
+            if !newer.data.contains_key(key) {
+                entries.add_action(key.clone(), UnseqEntryAction::Del);
+            }
+        }
+        Ok(self.delta(newer, entries))
+    }
+
+    /// Returns the changes since `revision`, computed from the logged changes.
+    ///
+    /// Returns `Err(InvalidSuccessor)` with the current revision if `revision` is newer, and
+    /// `Err(NoSuchEntry)` if the changes since `revision` are no longer logged, in which case
+    /// the whole Map has to be fetched.
+    pub fn changes_since(&self, revision: u64) -> Result<UnseqDelta> {
+        self.at_revision(revision)?.diff(self)
+    }
+
+    // Logs `change`, then checks the size, dropping the oldest logged changes while the Map is
+    // too large. On error, the log is left unchanged and the caller undoes the change.
+    fn commit(&mut self, change: Change<Bytes>) -> Result<()> {
+        let old_changes = self.changes.clone();
+        self.changes.record(change);
+        loop {
+            match self.check_size() {
+                Err(Error::ExceededSize) if self.changes.drop_oldest() => (),
+                Err(error) => {
+                    self.changes = old_changes;
+                    return Err(error);
+                }
+                Ok(()) => return Ok(()),
+            }
+        }
+    }
+
+    /// Applies changes computed by `diff`, verifying that the result is the Map they were
+    /// computed for. The Map is left unchanged on error.
+    ///
+    /// Returns `Err(InvalidEntryActions)` if the entry changes do not apply to this Map, and
+    /// `Err(InvalidSuccessor)` if the resulting Map is not the expected one.
+    pub fn apply_delta(&mut self, delta: UnseqDelta) -> Result<()> {
+        let mut change = self.entries_change(delta.entries.actions.keys());
+        if delta.version != self.version {
+            change.fields = self.fields_change().fields;
+        }
+        let mut updated = self.clone();
+        let mut errors = BTreeMap::new();
+        for (key, action) in delta.entries.actions.iter() {
+            match (action, updated.data.entry(key.clone())) {
+                (UnseqEntryAction::Ins(value), Entry::Vacant(entry)) => {
+                    let _ = entry.insert(value.clone());
+                }
+                (UnseqEntryAction::Update(value), Entry::Occupied(mut entry)) => {
+                    let _ = entry.insert(value.clone());
+                }
+                (UnseqEntryAction::Del, Entry::Occupied(entry)) => {
+                    let _ = entry.remove();
+                }
+                (UnseqEntryAction::Ins(_), Entry::Occupied(_)) => {
+                    let _ = errors.insert(key.clone(), EntryError::EntryExists(0));
+                }
+                (_, Entry::Vacant(_)) => {
+                    let _ = errors.insert(key.clone(), EntryError::NoSuchEntry);
+                }
+            }
+        }
+        if !errors.is_empty() {
+            return Err(Error::InvalidEntryActions(errors));
+        }
 
+        updated.apply_shell(delta)?;
+        updated.commit(change)?;
+        *self = updated;
         Ok(())
     }
 }
@@ -525,25 +1157,118 @@ impl SeqData {
             address: Address::Seq { name, tag },
             data: Default::default(),
             permissions: Default::default(),
+            anyone: Default::default(),
             version: 0,
+            revision: 0,
+            owners: Owners::single(owner),
             owner,
+            limits: Default::default(),
+            history: Default::default(),
+            changes: Default::default(),
+        }
+    }
+
+    /// Creates a new public sequenced Map, readable by anyone.
+    pub fn new_pub(name: XorName, tag: u64, owner: PublicKey) -> Self {
+        Self {
+            address: Address::PublicSeq { name, tag },
+            ..Self::new(name, tag, owner)
         }
     }
 
+    /// Creates a new public sequenced Map with entries and permissions, readable by anyone.
+    ///
+    /// Returns `Err(TooManyEntries)` or `Err(ExceededSize)` if the Map exceeds the default limits.
+    pub fn new_pub_with_data(
+        name: XorName,
+        tag: u64,
+        data: SeqEntries,
+        permissions: BTreeMap<PublicKey, PermissionSet>,
+        owner: PublicKey,
+    ) -> Result<Self> {
+        Ok(Self {
+            address: Address::PublicSeq { name, tag },
+            ..Self::new_with_data(name, tag, data, permissions, owner)?
+        })
+    }
+
     /// Creates a new sequenced Map with entries and permissions.
+    ///
+    /// Returns `Err(TooManyEntries)` or `Err(ExceededSize)` if the Map exceeds the default limits.
     pub fn new_with_data(
         name: XorName,
         tag: u64,
         data: SeqEntries,
         permissions: BTreeMap<PublicKey, PermissionSet>,
         owner: PublicKey,
-    ) -> Self {
-        Self {
+    ) -> Result<Self> {
+        let data = Self {
             address: Address::Seq { name, tag },
             data,
             permissions,
+            anyone: Default::default(),
             version: 0,
+            revision: 0,
+            owners: Owners::single(owner),
             owner,
+            limits: Default::default(),
+            history: Default::default(),
+            changes: Default::default(),
+        };
+        data.check_size()?;
+        Ok(data)
+    }
+
+    /// Enables keeping up to `limit` prior values of each key. The prior values count towards
+    /// the size of the Map, and the oldest ones are dropped to keep it within its limit.
+    pub fn with_history(mut self, limit: u64) -> Self {
+        self.history.prune(limit);
+        self
+    }
+
+    /// Returns the maximum number of prior values kept for each key. History is disabled if
+    /// zero.
+    pub fn history_limit(&self) -> u64 {
+        self.history.limit
+    }
+
+    /// Returns the prior values of `key`, oldest first.
+    ///
+    /// History is kept for deleted keys until it is pruned.
+    pub fn value_history(&self, key: &[u8]) -> Option<&[SeqValue]> {
+        self.history.values.get(key).map(|values| &values[..])
+    }
+
+    /// Returns the number of prior values kept for all keys. These count towards the size of
+    /// the Map.
+    pub fn history_len(&self) -> u64 {
+        self.history
+            .values
+            .values()
+            .map(|values| values.len() as u64)
+            .sum()
+    }
+
+    /// Sets the maximum number of prior values kept for each key, dropping the oldest values
+    /// over the new limit. A limit of zero disables history and drops all of it.
+    pub fn prune_history(&mut self, limit: u64) {
+        self.history.prune(limit)
+    }
+
+    /// Returns the shell of this Map (the fields without the data).
+    pub fn shell(&self) -> Self {
+        Self {
+            address: self.address.clone(),
+            data: BTreeMap::new(),
+            permissions: self.permissions.clone(),
+            anyone: self.anyone.clone(),
+            version: self.version,
+            revision: self.revision,
+            owner: self.owner,
+            owners: self.owners.clone(),
+            limits: self.limits,
+            history: History::new(self.history.limit),
+            changes: ChangeLog::new(self.changes.limit),
         }
     }
 
@@ -562,15 +1287,49 @@ impl SeqData {
         &self.data
     }
 
+    /// Returns up to `limit` entries in `range`, starting from `continue_from` if given.
+    pub fn entries_in_range(
+        &self,
+        range: &KeyRange,
+        limit: u64,
+        continue_from: Option<&[u8]>,
+    ) -> Page<SeqEntries> {
+        range_page(&self.data, range, limit, continue_from)
+    }
+
     /// Removes and returns all entries
     pub fn take_entries(&mut self) -> SeqEntries {
         mem::replace(&mut self.data, BTreeMap::new())
     }
 
+    /// Converts into an unsequenced Map at the unsequenced address with the same name and tag.
+    ///
+    /// Entries, permissions and owners are kept. Entry versions, value history and logged
+    /// changes are dropped.
+    pub fn into_unseq(self) -> UnseqData {
+        UnseqData {
+            address: self.address.with_kind(Kind::Unseq),
+            data: self
+                .data
+                .into_iter()
+                .map(|(key, value)| (key, value.data))
+                .collect(),
+            permissions: self.permissions,
+            anyone: self.anyone,
+            version: self.version,
+            revision: self.revision,
+            owner: self.owner,
+            owners: self.owners,
+            limits: self.limits,
+            changes: ChangeLog::new(self.changes.limit),
+        }
+    }
+
     /// Mutates entries (key + value pairs) in bulk.
     ///
     /// Returns `Err(InvalidEntryActions)` if the mutation parameters are invalid.
     pub fn mutate_entries(&mut self, actions: SeqEntryActions, requester: PublicKey) -> Result<()> {
+        let change = self.entries_change(actions.actions.keys());
         // Deconstruct actions into inserts, updates, and deletes
         let (insert, update, delete) = actions.actions.into_iter().fold(
             (BTreeMap::new(), BTreeMap::new(), BTreeMap::new()),
@@ -590,7 +1349,7 @@ impl SeqData {
             },
         );
 
-        if *self.owner() != requester
+        if !self.is_owner(&requester)
             && ((!insert.is_empty() && !self.is_action_allowed(&requester, Action::Insert))
                 || (!update.is_empty() && !self.is_action_allowed(&requester, Action::Update))
                 || (!delete.is_empty() && !self.is_action_allowed(&requester, Action::Delete)))
@@ -599,6 +1358,7 @@ impl SeqData {
         }
 
         let mut new_data = self.data.clone();
+        let mut new_history = self.history.clone();
         let mut errors = BTreeMap::new();
 
         for (key, val) in insert {
@@ -620,7 +1380,8 @@ impl SeqData {
                 Entry::Occupied(mut entry) => {
                     let current_version = entry.get().version;
                     if val.version == current_version + 1 {
-                        let _ = entry.insert(val);
+                        let key = entry.key().clone();
+                        new_history.record(key, entry.insert(val));
                     } else {
                         let _ = errors.insert(
                             entry.key().clone(),
@@ -639,7 +1400,7 @@ impl SeqData {
                 Entry::Occupied(entry) => {
                     let current_version = entry.get().version;
                     if version == current_version + 1 {
-                        let _ = new_data.remove(&key);
+                        new_history.record(key, entry.remove());
                     } else {
                         let _ = errors.insert(
                             entry.key().clone(),
@@ -657,27 +1418,527 @@ impl SeqData {
             return Err(Error::InvalidEntryActions(errors));
         }
 
-        let _old_data = mem::replace(&mut self.data, new_data);
+        let old_data = mem::replace(&mut self.data, new_data);
+        let old_history = mem::replace(&mut self.history, new_history);
+        if let Err(error) = self.commit(change) {
+            self.data = old_data;
+            self.history = old_history;
+            return Err(error);
+        }
+        self.revision += 1;
 
         Ok(())
     }
-}
 
-/// Kind of a Map.
-#[derive(Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Hash, Serialize, Deserialize, Debug)]
-pub enum Kind {
-    /// Unsequenced.
-    Unseq,
-    /// Sequenced.
-    Seq,
-}
+    /// Applies the actions of `transaction` if all its guards hold. Guards may be on keys the
+    /// actions do not touch, and reading them requires the `Read` permission.
+    ///
+    /// Returns `Err(InvalidEntryActions)` with an error for each failed guard, or for each
+    /// invalid action if all guards hold.
+    pub fn transact(&mut self, transaction: Transaction, requester: PublicKey) -> Result<()> {
+        if !self.is_owner(&requester)
+            && !transaction.guards.is_empty()
+            && !self.is_action_allowed(&requester, Action::Read)
+        {
+            return Err(Error::AccessDenied);
+        }
 
-impl Kind {
-    /// Creates `Kind` from a `sequenced` flag.
-    pub fn from_flag(sequenced: bool) -> Self {
-        if sequenced {
-            Kind::Seq
-        } else {
+        let errors: BTreeMap<_, _> = transaction
+            .guards
+            .iter()
+            .filter_map(|(key, guard)| Some((key.clone(), guard.check(self.data.get(key))?)))
+            .collect();
+        if !errors.is_empty() {
+            return Err(Error::InvalidEntryActions(errors));
+        }
+
+        self.mutate_entries(transaction.actions, requester)
+    }
+
+    /// Returns the changes from this Map to `newer`, a later state of the same Map.
+    ///
+    /// Returns `Err(InvalidOperation)` if `newer` has a different address.
+    pub fn diff(&self, newer: &Self) -> Result<SeqDelta> {
+        if newer.address != self.address {
+            return Err(Error::InvalidOperation);
+        }
+        let mut entries = SeqEntryActions::new();
+        for (key, value) in &newer.data {
+            match self.data.get(key) {
+                None => entries.add_action(key.clone(), SeqEntryAction::Ins(value.clone())),
+                Some(old_value) if old_value != value => {
+                    entries.add_action(key.clone(), SeqEntryAction::Update(value.clone()))
+                }
+                Some(_) => (),
+            }
+        }
+        for (key, old_value) in &self.data {
+            if !newer.data.contains_key(key) {
+                entries.add_action(key.clone(), SeqEntryAction::Del(old_value.version + 1));
+            }
+        }
+        Ok(self.delta(newer, entries))
+    }
+
+    /// Returns the changes since `revision`, computed from the logged changes.
+    ///
+    /// Returns `Err(InvalidSuccessor)` with the current revision if `revision` is newer, and
+    /// `Err(NoSuchEntry)` if the changes since `revision` are no longer logged, in which case
+    /// the whole Map has to be fetched.
+    pub fn changes_since(&self, revision: u64) -> Result<SeqDelta> {
+        self.at_revision(revision)?.diff(self)
+    }
+
+    // Logs `change`, then checks the size, dropping the oldest logged changes, then the oldest
+    // prior values, while the Map is too large. On error, the log and the history are left
+    // unchanged and the caller undoes the change.
+    fn commit(&mut self, change: Change<SeqValue>) -> Result<()> {
+        let old_changes = self.changes.clone();
+        let old_history = self.history.clone();
+        self.changes.record(change);
+        loop {
+            match self.check_size() {
+                Err(Error::ExceededSize)
+                    if self.changes.drop_oldest() || self.history.drop_oldest() => {}
+                Err(error) => {
+                    self.changes = old_changes;
+                    self.history = old_history;
+                    return Err(error);
+                }
+                Ok(()) => return Ok(()),
+            }
+        }
+    }
+
+    /// Applies changes computed by `diff`, verifying that the result is the Map they were
+    /// computed for. The Map is left unchanged on error.
+    ///
+    /// Entry versions may skip ahead, as a delta can span several changes to an entry.
+    /// Returns `Err(InvalidEntryActions)` if the entry changes do not apply to this Map, and
+    /// `Err(InvalidSuccessor)` if the resulting Map is not the expected one.
+    pub fn apply_delta(&mut self, delta: SeqDelta) -> Result<()> {
+        let mut change = self.entries_change(delta.entries.actions.keys());
+        if delta.version != self.version {
+            change.fields = self.fields_change().fields;
+        }
+        let mut updated = self.clone();
+        let mut errors = BTreeMap::new();
+        for (key, action) in delta.entries.actions.iter() {
+            match (action, updated.data.entry(key.clone())) {
+                (SeqEntryAction::Ins(value), Entry::Vacant(entry)) => {
+                    let _ = entry.insert(value.clone());
+                }
+                (SeqEntryAction::Update(value), Entry::Occupied(mut entry))
+                    if value.version > entry.get().version =>
+                {
+                    updated
+                        .history
+                        .record(key.clone(), entry.insert(value.clone()));
+                }
+                (SeqEntryAction::Del(version), Entry::Occupied(entry))
+                    if *version > entry.get().version =>
+                {
+                    updated.history.record(key.clone(), entry.remove());
+                }
+                (SeqEntryAction::Ins(_), Entry::Occupied(entry)) => {
+                    let _ = errors.insert(
+                        key.clone(),
+                        EntryError::EntryExists(entry.get().version as u8),
+                    );
+                }
+                (_, Entry::Occupied(entry)) => {
+                    let _ = errors.insert(
+                        key.clone(),
+                        EntryError::InvalidSuccessor(entry.get().version as u8),
+                    );
+                }
+                (_, Entry::Vacant(_)) => {
+                    let _ = errors.insert(key.clone(), EntryError::NoSuchEntry);
+                }
+            }
+        }
+        if !errors.is_empty() {
+            return Err(Error::InvalidEntryActions(errors));
+        }
+
+        updated.apply_shell(delta)?;
+        updated.commit(change)?;
+        *self = updated;
+        Ok(())
+    }
+}
+
+/// Maximum number of entries in a Map.
+///
+/// Bounds the number of entries vaults go through for a single request, such as listing the
+/// entries or computing a delta, however small the entries are. It is the number of 1 KiB
+/// entries that fit in the maximum size of a Map.
+pub const MAX_MAP_ENTRIES: u64 = MAX_MAP_SIZE_IN_BYTES / 1024;
+/// Maximum size of a Map key.
+pub const MAX_MAP_KEY_SIZE_IN_BYTES: u64 = 1024;
+/// Maximum size of a Map value.
+pub const MAX_MAP_VALUE_SIZE_IN_BYTES: u64 = 100 * 1024;
+/// Maximum size of a serialised Map.
+pub const MAX_MAP_SIZE_IN_BYTES: u64 = 1024 * 1024;
+
+/// Size limits of a Map. Each limit can be lowered from its network maximum, which is also its
+/// default, but not raised.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct Limits {
+    /// Maximum number of entries.
+    pub max_entries: u64,
+    /// Maximum size of a key.
+    pub max_key_size: u64,
+    /// Maximum size of a value.
+    pub max_value_size: u64,
+    /// Maximum size of the serialised Map, including its shell, any value history and any
+    /// logged changes.
+    pub max_size: u64,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Self {
+            max_entries: MAX_MAP_ENTRIES,
+            max_key_size: MAX_MAP_KEY_SIZE_IN_BYTES,
+            max_value_size: MAX_MAP_VALUE_SIZE_IN_BYTES,
+            max_size: MAX_MAP_SIZE_IN_BYTES,
+        }
+    }
+}
+
+impl Limits {
+    // Checks that the limits are within the network maximums.
+    pub(crate) fn check(&self) -> Result<()> {
+        if self.max_entries > MAX_MAP_ENTRIES {
+            return Err(Error::TooManyEntries);
+        }
+        if self.max_key_size > MAX_MAP_KEY_SIZE_IN_BYTES
+            || self.max_value_size > MAX_MAP_VALUE_SIZE_IN_BYTES
+            || self.max_size > MAX_MAP_SIZE_IN_BYTES
+        {
+            return Err(Error::ExceededSize);
+        }
+        Ok(())
+    }
+}
+
+// Size of the content of a value, checked against `Limits::max_value_size`.
+trait ValueSize {
+    fn value_size(&self) -> usize;
+}
+
+impl ValueSize for Bytes {
+    fn value_size(&self) -> usize {
+        self.len()
+    }
+}
+
+impl ValueSize for SeqValue {
+    fn value_size(&self) -> usize {
+        self.data.len()
+    }
+}
+
+/// Owners of a Map, of which `threshold` must sign owner-level operations.
+#[derive(Hash, Eq, PartialEq, PartialOrd, Ord, Clone, Debug)]
+pub struct Owners {
+    keys: BTreeSet<PublicKey>,
+    threshold: usize,
+}
+
+impl Owners {
+    /// Creates a set of owners, of which `threshold` must sign owner-level operations.
+    ///
+    /// Returns `Err(InvalidOwners)` if there are no keys, or if the threshold is zero or greater
+    /// than the number of keys.
+    pub fn new(keys: BTreeSet<PublicKey>, threshold: usize) -> Result<Self> {
+        if threshold == 0 || threshold > keys.len() {
+            return Err(Error::InvalidOwners);
+        }
+        Ok(Self { keys, threshold })
+    }
+
+    /// Creates a set with a single owner.
+    pub fn single(key: PublicKey) -> Self {
+        Self {
+            keys: vec![key].into_iter().collect(),
+            threshold: 1,
+        }
+    }
+
+    /// Returns the owner keys.
+    pub fn keys(&self) -> &BTreeSet<PublicKey> {
+        &self.keys
+    }
+
+    /// Returns the number of owners which must sign owner-level operations.
+    pub fn threshold(&self) -> usize {
+        self.threshold
+    }
+
+    /// Returns `true` if `key` is one of the owners.
+    pub fn contains(&self, key: &PublicKey) -> bool {
+        self.keys.contains(key)
+    }
+}
+
+impl Serialize for Owners {
+    fn serialize<S: Serializer>(&self, serialiser: S) -> std::result::Result<S::Ok, S::Error> {
+        (&self.keys, self.threshold).serialize(serialiser)
+    }
+}
+
+// Owners are validated when deserialised, so that a Map never has a threshold it cannot meet.
+impl<'de> Deserialize<'de> for Owners {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        let (keys, threshold): (BTreeSet<PublicKey>, usize) =
+            Deserialize::deserialize(deserializer)?;
+        Owners::new(keys, threshold).map_err(de::Error::custom)
+    }
+}
+
+/// Owner-level operation on a Map.
+#[derive(Hash, Eq, PartialEq, PartialOrd, Ord, Clone, Serialize, Deserialize, Debug)]
+pub enum OwnerOp {
+    /// Replaces the owners. `owner` must be one of `owners`.
+    ChangeOwners {
+        /// New owner.
+        owner: PublicKey,
+        /// New owners.
+        owners: Owners,
+    },
+    /// Inserts or updates permissions for a user.
+    SetUserPermissions {
+        /// User to set permissions for.
+        user: PublicKey,
+        /// New permissions.
+        permissions: PermissionSet,
+    },
+    /// Deletes permissions for a user.
+    DelUserPermissions {
+        /// User to delete permissions for.
+        user: PublicKey,
+    },
+    /// Sets the permissions for anyone on a public Map.
+    SetAnyonePermissions {
+        /// New permissions.
+        permissions: PermissionSet,
+    },
+    /// Converts a sequenced Map into an unsequenced one, or the other way round, as
+    /// `Data::convert` does for a single owner.
+    Convert,
+}
+
+/// Owner-level operation with the signatures of the owners approving it, collected until there
+/// are enough to meet the threshold.
+#[derive(Hash, Eq, PartialEq, PartialOrd, Ord, Clone, Serialize, Deserialize, Debug)]
+pub struct SignedOwnerOp {
+    address: Address,
+    op: OwnerOp,
+    version: u64,
+    signatures: BTreeMap<PublicKey, Signature>,
+}
+
+impl SignedOwnerOp {
+    /// Creates an unsigned operation on the Map at `address`, with the new `version` of the Map
+    /// fields.
+    pub fn new(address: Address, op: OwnerOp, version: u64) -> Self {
+        Self {
+            address,
+            op,
+            version,
+            signatures: BTreeMap::new(),
+        }
+    }
+
+    /// Returns the address of the Map.
+    pub fn address(&self) -> &Address {
+        &self.address
+    }
+
+    /// Returns the operation.
+    pub fn op(&self) -> &OwnerOp {
+        &self.op
+    }
+
+    /// Returns the new version of the Map fields.
+    pub fn version(&self) -> u64 {
+        self.version
+    }
+
+    /// Returns the collected signatures.
+    pub fn signatures(&self) -> &BTreeMap<PublicKey, Signature> {
+        &self.signatures
+    }
+
+    /// Returns the bytes for owners to sign.
+    pub fn signing_bytes(&self) -> Vec<u8> {
+        utils::serialise(&(&self.address, &self.op, self.version))
+    }
+
+    /// Adds the signature of `key`.
+    ///
+    /// Returns `Err(InvalidSignature)` if the signature is not valid for the operation.
+    pub fn add_signature(&mut self, key: PublicKey, signature: Signature) -> Result<()> {
+        key.verify(&signature, self.signing_bytes())?;
+        let _ = self.signatures.insert(key, signature);
+        Ok(())
+    }
+
+    // Returns the number of `owners` with a valid signature.
+    fn valid_signers(&self, owners: &Owners) -> usize {
+        let bytes = self.signing_bytes();
+        self.signatures
+            .iter()
+            .filter(|(key, signature)| {
+                owners.contains(key) && key.verify(signature, &bytes).is_ok()
+            })
+            .count()
+    }
+}
+
+/// Prior values of the keys of a sequenced Map.
+#[derive(Hash, Eq, PartialEq, PartialOrd, Ord, Clone, Serialize, Deserialize, Debug, Default)]
+struct History {
+    /// Maximum number of prior values kept for each key. History is disabled if zero.
+    limit: u64,
+    /// Prior values of each key, oldest first.
+    values: BTreeMap<Vec<u8>, Vec<SeqValue>>,
+}
+
+impl History {
+    fn new(limit: u64) -> Self {
+        Self {
+            limit,
+            values: BTreeMap::new(),
+        }
+    }
+
+    // Keeps `value` as the latest prior value of `key`, dropping the oldest one over the limit.
+    fn record(&mut self, key: Vec<u8>, value: SeqValue) {
+        if self.limit == 0 {
+            return;
+        }
+        let values = self.values.entry(key).or_insert_with(Vec::new);
+        values.push(value);
+        if values.len() as u64 > self.limit {
+            let _ = values.remove(0);
+        }
+    }
+
+    fn prune(&mut self, limit: u64) {
+        self.limit = limit;
+        for values in self.values.values_mut() {
+            let excess = values.len().saturating_sub(limit as usize);
+            let _ = values.drain(..excess);
+        }
+        self.values.retain(|_, values| !values.is_empty());
+    }
+
+    // Drops the oldest prior value of the key with the most of them. Returns `false` if there
+    // was none.
+    fn drop_oldest(&mut self) -> bool {
+        let key = match self.values.iter().max_by_key(|(_, values)| values.len()) {
+            Some((key, _)) => key.clone(),
+            None => return false,
+        };
+        if let Some(values) = self.values.get_mut(&key) {
+            let _ = values.remove(0);
+            if values.is_empty() {
+                let _ = self.values.remove(&key);
+            }
+        }
+        true
+    }
+}
+
+/// Fields of a Map other than its entries, as logged to undo changes to them.
+#[derive(Hash, Eq, PartialEq, PartialOrd, Ord, Clone, Serialize, Deserialize, Debug)]
+struct Fields {
+    permissions: BTreeMap<PublicKey, PermissionSet>,
+    anyone: PermissionSet,
+    version: u64,
+    owner: PublicKey,
+    owners: Owners,
+}
+
+/// A logged change to a Map, with what is needed to undo it.
+#[derive(Hash, Eq, PartialEq, PartialOrd, Ord, Clone, Serialize, Deserialize, Debug)]
+struct Change<V> {
+    /// Revision of the Map before the change.
+    revision: u64,
+    /// Prior values of the changed entries, with `None` for inserted ones.
+    entries: BTreeMap<Vec<u8>, Option<V>>,
+    /// Prior fields, if they changed.
+    fields: Option<Fields>,
+}
+
+/// Recent changes to a Map, from which the changes since a recent revision are computed.
+#[derive(Hash, Eq, PartialEq, PartialOrd, Ord, Clone, Serialize, Deserialize, Debug)]
+struct ChangeLog<V> {
+    /// Maximum number of changes kept. The log is disabled if zero.
+    limit: u64,
+    /// Changes, oldest first.
+    changes: Vec<Change<V>>,
+}
+
+impl<V> Default for ChangeLog<V> {
+    fn default() -> Self {
+        Self::new(0)
+    }
+}
+
+impl<V> ChangeLog<V> {
+    fn new(limit: u64) -> Self {
+        Self {
+            limit,
+            changes: Vec::new(),
+        }
+    }
+
+    // Keeps `change` as the latest change, dropping the oldest one over the limit.
+    fn record(&mut self, change: Change<V>) {
+        if self.limit == 0 {
+            return;
+        }
+        self.changes.push(change);
+        if self.changes.len() as u64 > self.limit {
+            let _ = self.changes.remove(0);
+        }
+    }
+
+    fn prune(&mut self, limit: u64) {
+        self.limit = limit;
+        let excess = self.changes.len().saturating_sub(limit as usize);
+        let _ = self.changes.drain(..excess);
+    }
+
+    // Drops the oldest change. Returns `false` if there was none.
+    fn drop_oldest(&mut self) -> bool {
+        if self.changes.is_empty() {
+            return false;
+        }
+        let _ = self.changes.remove(0);
+        true
+    }
+}
+
+/// Kind of a Map.
+#[derive(Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Hash, Serialize, Deserialize, Debug)]
+pub enum Kind {
+    /// Unsequenced.
+    Unseq,
+    /// Sequenced.
+    Seq,
+}
+
+impl Kind {
+    /// Creates `Kind` from a `sequenced` flag.
+    pub fn from_flag(sequenced: bool) -> Self {
+        if sequenced {
+            Kind::Seq
+        } else {
             Kind::Unseq
         }
     }
@@ -710,6 +1971,20 @@ pub enum Address {
         /// Tag.
         tag: u64,
     },
+    /// Public unsequenced namespace.
+    PublicUnseq {
+        /// Name.
+        name: XorName,
+        /// Tag.
+        tag: u64,
+    },
+    /// Public sequenced namespace.
+    PublicSeq {
+        /// Name.
+        name: XorName,
+        /// Tag.
+        tag: u64,
+    },
 }
 
 impl Address {
@@ -721,25 +1996,47 @@ impl Address {
         }
     }
 
+    /// Constructs a public `Address` given `kind`, `name`, and `tag`.
+    pub fn from_kind_pub(kind: Kind, name: XorName, tag: u64) -> Self {
+        match kind {
+            Kind::Seq => Address::PublicSeq { name, tag },
+            Kind::Unseq => Address::PublicUnseq { name, tag },
+        }
+    }
+
     /// Returns the kind.
     pub fn kind(&self) -> Kind {
         match self {
-            Address::Seq { .. } => Kind::Seq,
-            Address::Unseq { .. } => Kind::Unseq,
+            Address::Seq { .. } | Address::PublicSeq { .. } => Kind::Seq,
+            Address::Unseq { .. } | Address::PublicUnseq { .. } => Kind::Unseq,
         }
     }
 
     /// Returns the name.
     pub fn name(&self) -> &XorName {
         match self {
-            Address::Unseq { ref name, .. } | Address::Seq { ref name, .. } => name,
+            Address::Unseq { ref name, .. }
+            | Address::Seq { ref name, .. }
+            | Address::PublicUnseq { ref name, .. }
+            | Address::PublicSeq { ref name, .. } => name,
         }
     }
 
     /// Returns the tag.
     pub fn tag(&self) -> u64 {
         match self {
-            Address::Unseq { tag, .. } | Address::Seq { tag, .. } => *tag,
+            Address::Unseq { tag, .. }
+            | Address::Seq { tag, .. }
+            | Address::PublicUnseq { tag, .. }
+            | Address::PublicSeq { tag, .. } => *tag,
+        }
+    }
+
+    /// Returns `true` if public, i.e. readable by anyone.
+    pub fn is_pub(&self) -> bool {
+        match self {
+            Address::PublicUnseq { .. } | Address::PublicSeq { .. } => true,
+            Address::Unseq { .. } | Address::Seq { .. } => false,
         }
     }
 
@@ -753,6 +2050,15 @@ impl Address {
         self.kind().is_unseq()
     }
 
+    /// Returns the address with the same name, tag and visibility, but of the given `kind`.
+    pub fn with_kind(&self, kind: Kind) -> Self {
+        if self.is_pub() {
+            Self::from_kind_pub(kind, *self.name(), self.tag())
+        } else {
+            Self::from_kind(kind, *self.name(), self.tag())
+        }
+    }
+
     /// Returns the Address serialised and encoded in z-base-32.
     pub fn encode_to_zbase32(&self) -> String {
         utils::encode(&self)
@@ -815,6 +2121,14 @@ impl Data {
         }
     }
 
+    /// Returns the revision, which any change to the Map increases.
+    pub fn revision(&self) -> u64 {
+        match self {
+            Data::Seq(data) => data.revision(),
+            Data::Unseq(data) => data.revision(),
+        }
+    }
+
     /// Returns all the keys in the data.
     pub fn keys(&self) -> BTreeSet<Vec<u8>> {
         match self {
@@ -823,6 +2137,74 @@ impl Data {
         }
     }
 
+    /// Returns up to `limit` keys in `range`, starting from `continue_from` if given.
+    pub fn keys_in_range(
+        &self,
+        range: &KeyRange,
+        limit: u64,
+        continue_from: Option<&[u8]>,
+    ) -> KeysPage {
+        match self {
+            Data::Seq(data) => data.keys_in_range(range, limit, continue_from),
+            Data::Unseq(data) => data.keys_in_range(range, limit, continue_from),
+        }
+    }
+
+    /// Returns up to `limit` entries in `range`, starting from `continue_from` if given.
+    pub fn entries_in_range(
+        &self,
+        range: &KeyRange,
+        limit: u64,
+        continue_from: Option<&[u8]>,
+    ) -> EntriesPage {
+        match self {
+            Data::Seq(data) => {
+                let page = data.entries_in_range(range, limit, continue_from);
+                Page {
+                    items: Entries::Seq(page.items),
+                    continue_from: page.continue_from,
+                }
+            }
+            Data::Unseq(data) => {
+                let page = data.entries_in_range(range, limit, continue_from);
+                Page {
+                    items: Entries::Unseq(page.items),
+                    continue_from: page.continue_from,
+                }
+            }
+        }
+    }
+
+    /// Returns the size limits.
+    pub fn limits(&self) -> &Limits {
+        match self {
+            Data::Seq(data) => data.limits(),
+            Data::Unseq(data) => data.limits(),
+        }
+    }
+
+    /// Returns size of this data after serialisation.
+    pub fn serialised_size(&self) -> u64 {
+        match self {
+            Data::Seq(data) => data.serialised_size(),
+            Data::Unseq(data) => data.serialised_size(),
+        }
+    }
+
+    /// Returns `true` if the size is valid.
+    pub fn validate_size(&self) -> bool {
+        self.check_size().is_ok()
+    }
+
+    /// Checks that the Map is within its size limits, and that the limits are within the
+    /// network maximums.
+    pub fn check_size(&self) -> Result<()> {
+        match self {
+            Data::Seq(data) => data.check_size(),
+            Data::Unseq(data) => data.check_size(),
+        }
+    }
+
     /// Returns the shell of the data.
     pub fn shell(&self) -> Self {
         match self {
@@ -868,6 +2250,31 @@ impl Data {
         }
     }
 
+    /// Returns `true` if public.
+    pub fn is_pub(&self) -> bool {
+        self.address().is_pub()
+    }
+
+    /// Gets the permissions for anyone without their own permissions.
+    pub fn anyone_permissions(&self) -> &PermissionSet {
+        match self {
+            Data::Seq(data) => data.anyone_permissions(),
+            Data::Unseq(data) => data.anyone_permissions(),
+        }
+    }
+
+    /// Sets the permissions for anyone without their own permissions.
+    pub fn set_anyone_permissions(
+        &mut self,
+        permissions: PermissionSet,
+        version: u64,
+    ) -> Result<()> {
+        match self {
+            Data::Seq(data) => data.set_anyone_permissions(permissions, version),
+            Data::Unseq(data) => data.set_anyone_permissions(permissions, version),
+        }
+    }
+
     /// Checks permissions for given `action` for the provided user.
     pub fn check_permissions(&self, action: Action, requester: PublicKey) -> Result<()> {
         match self {
@@ -884,6 +2291,43 @@ impl Data {
         }
     }
 
+    /// Returns all the owners.
+    pub fn owners(&self) -> &Owners {
+        match self {
+            Data::Seq(data) => data.owners(),
+            Data::Unseq(data) => data.owners(),
+        }
+    }
+
+    /// Applies an owner-level operation signed by at least the threshold of owners.
+    ///
+    /// A conversion turns the Map into one of the other kind, at its new address, with the
+    /// version of the operation.
+    pub fn apply_owner_op(&mut self, op: SignedOwnerOp) -> Result<()> {
+        if op.op == OwnerOp::Convert {
+            if op.address != *self.address() {
+                return Err(Error::InvalidOperation);
+            }
+            if op.valid_signers(self.owners()) < self.owners().threshold {
+                return Err(Error::AccessDenied);
+            }
+            if op.version != self.version() + 1 {
+                return Err(Error::InvalidSuccessor(self.version()));
+            }
+            let mut converted = self.clone().into_other_kind()?;
+            match converted {
+                Data::Seq(ref mut data) => data.version = op.version,
+                Data::Unseq(ref mut data) => data.version = op.version,
+            }
+            *self = converted;
+            return Ok(());
+        }
+        match self {
+            Data::Seq(data) => data.apply_owner_op(op),
+            Data::Unseq(data) => data.apply_owner_op(op),
+        }
+    }
+
     /// Returns the owner key.
     pub fn owner(&self) -> PublicKey {
         match self {
@@ -892,6 +2336,25 @@ impl Data {
         }
     }
 
+    /// Converts a sequenced Map into an unsequenced one, or the other way round, on behalf of
+    /// the owner. The converted Map has a new address, of the other kind. Entries of a
+    /// sequenced Map get version 0, while converting into an unsequenced Map drops the entry
+    /// versions and value history.
+    ///
+    /// Returns `Err(AccessDenied)` if `requester` cannot act alone as the owner, in which case
+    /// the Map is converted with an `OwnerOp::Convert` signed by enough owners.
+    pub fn convert(&self, requester: PublicKey) -> Result<Data> {
+        self.check_is_owner(requester)?;
+        self.clone().into_other_kind()
+    }
+
+    fn into_other_kind(self) -> Result<Data> {
+        match self {
+            Data::Seq(data) => Ok(Data::Unseq(data.into_unseq())),
+            Data::Unseq(data) => Ok(Data::Seq(data.into_seq()?)),
+        }
+    }
+
     /// Mutates entries (key + value pairs) in bulk.
     pub fn mutate_entries(&mut self, actions: EntryActions, requester: PublicKey) -> Result<()> {
         match self {
@@ -909,6 +2372,43 @@ impl Data {
 
         Err(Error::InvalidOperation)
     }
+
+    /// Applies the actions of `transaction` if all its guards hold.
+    ///
+    /// Returns `Err(InvalidOperation)` for unsequenced Maps.
+    pub fn transact(&mut self, transaction: Transaction, requester: PublicKey) -> Result<()> {
+        match self {
+            Data::Seq(data) => data.transact(transaction, requester),
+            Data::Unseq(_) => Err(Error::InvalidOperation),
+        }
+    }
+
+    /// Returns the changes from this Map to `newer`, a later state of the same Map.
+    pub fn diff(&self, newer: &Data) -> Result<Delta> {
+        match (self, newer) {
+            (Data::Seq(data), Data::Seq(newer)) => Ok(Delta::Seq(data.diff(newer)?)),
+            (Data::Unseq(data), Data::Unseq(newer)) => Ok(Delta::Unseq(data.diff(newer)?)),
+            _ => Err(Error::InvalidOperation),
+        }
+    }
+
+    /// Returns the changes since `revision`, computed from the logged changes.
+    pub fn changes_since(&self, revision: u64) -> Result<Delta> {
+        match self {
+            Data::Seq(data) => Ok(Delta::Seq(data.changes_since(revision)?)),
+            Data::Unseq(data) => Ok(Delta::Unseq(data.changes_since(revision)?)),
+        }
+    }
+
+    /// Applies changes computed by `diff`, verifying that the result is the Map they were
+    /// computed for.
+    pub fn apply_delta(&mut self, delta: Delta) -> Result<()> {
+        match (self, delta) {
+            (Data::Seq(data), Delta::Seq(delta)) => data.apply_delta(delta),
+            (Data::Unseq(data), Delta::Unseq(delta)) => data.apply_delta(delta),
+            _ => Err(Error::InvalidOperation),
+        }
+    }
 }
 
 impl From<SeqData> for Data {
@@ -958,9 +2458,9 @@ impl SeqEntryAction {
 #[derive(Hash, Eq, PartialEq, PartialOrd, Ord, Clone, Serialize, Deserialize, Debug)]
 pub enum UnseqEntryAction {
     /// Inserts a new unsequenced entry.
-    Ins(Vec<u8>),
+    Ins(Bytes),
     /// Updates an entry with a new value.
-    Update(Vec<u8>),
+    Update(Bytes),
     /// Deletes an entry.
     Del,
 }
@@ -996,7 +2496,7 @@ impl SeqEntryActions {
         let _ = self.actions.insert(
             key,
             SeqEntryAction::Ins(SeqValue {
-                data: content,
+                data: content.into(),
                 version,
             }),
         );
@@ -1011,7 +2511,7 @@ impl SeqEntryActions {
         let _ = self.actions.insert(
             key,
             SeqEntryAction::Update(SeqValue {
-                data: content,
+                data: content.into(),
                 version,
             }),
         );
@@ -1045,6 +2545,78 @@ impl From<BTreeMap<Vec<u8>, SeqEntryAction>> for SeqEntryActions {
     }
 }
 
+/// Precondition on a key of a sequenced Map, checked before the actions of a transaction are
+/// applied.
+#[derive(Hash, Eq, PartialEq, PartialOrd, Ord, Clone, Copy, Serialize, Deserialize, Debug)]
+pub enum Guard {
+    /// The key does not exist.
+    Absent,
+    /// The key exists at the given version.
+    Version(u64),
+    /// The key exists with a value of the given SHA3-256 hash.
+    ValueHash([u8; 32]),
+}
+
+impl Guard {
+    /// Creates a guard that the key exists with the given value.
+    pub fn value(value: &[u8]) -> Self {
+        Guard::ValueHash(tiny_keccak::sha3_256(value))
+    }
+
+    // Returns the error for `entry` if it does not satisfy the guard.
+    fn check(&self, entry: Option<&SeqValue>) -> Option<EntryError> {
+        match (self, entry) {
+            (Guard::Absent, None) => None,
+            (Guard::Absent, Some(value)) => Some(EntryError::EntryExists(value.version as u8)),
+            (_, None) => Some(EntryError::NoSuchEntry),
+            (Guard::Version(version), Some(value)) if *version == value.version => None,
+            (Guard::Version(_), Some(value)) => {
+                Some(EntryError::InvalidSuccessor(value.version as u8))
+            }
+            (Guard::ValueHash(hash), Some(value)) => {
+                if *hash == tiny_keccak::sha3_256(&value.data) {
+                    None
+                } else {
+                    Some(EntryError::ValueMismatch)
+                }
+            }
+        }
+    }
+}
+
+/// Entry actions on a sequenced Map, applied only if all the guards hold.
+#[derive(Hash, Eq, PartialEq, PartialOrd, Ord, Clone, Serialize, Deserialize, Debug, Default)]
+pub struct Transaction {
+    guards: BTreeMap<Vec<u8>, Guard>,
+    actions: SeqEntryActions,
+}
+
+impl Transaction {
+    /// Creates a transaction applying `actions`, with no guards.
+    pub fn new(actions: SeqEntryActions) -> Self {
+        Self {
+            guards: BTreeMap::new(),
+            actions,
+        }
+    }
+
+    /// Adds a guard on `key`, replacing any previous guard on it.
+    pub fn guard(mut self, key: Vec<u8>, guard: Guard) -> Self {
+        let _ = self.guards.insert(key, guard);
+        self
+    }
+
+    /// Returns the guards.
+    pub fn guards(&self) -> &BTreeMap<Vec<u8>, Guard> {
+        &self.guards
+    }
+
+    /// Returns the actions.
+    pub fn actions(&self) -> &SeqEntryActions {
+        &self.actions
+    }
+}
+
 /// Unsequenced Entry Actions for given entry keys.
 #[derive(Hash, Eq, PartialEq, PartialOrd, Ord, Clone, Serialize, Deserialize, Debug, Default)]
 pub struct UnseqEntryActions {
@@ -1071,13 +2643,17 @@ impl UnseqEntryActions {
 
     /// Insert a new key-value pair
     pub fn ins(mut self, key: Vec<u8>, content: Vec<u8>) -> Self {
-        let _ = self.actions.insert(key, UnseqEntryAction::Ins(content));
+        let _ = self
+            .actions
+            .insert(key, UnseqEntryAction::Ins(content.into()));
         self
     }
 
     /// Update existing key-value pair
     pub fn update(mut self, key: Vec<u8>, content: Vec<u8>) -> Self {
-        let _ = self.actions.insert(key, UnseqEntryAction::Update(content));
+        let _ = self
+            .actions
+            .insert(key, UnseqEntryAction::Update(content.into()));
         self
     }
 
@@ -1136,10 +2712,172 @@ impl From<UnseqEntryActions> for EntryActions {
     }
 }
 
+/// Changes between two states of a Map, as computed by `diff`.
+#[derive(Hash, Eq, PartialEq, PartialOrd, Ord, Clone, Serialize, Deserialize, Debug)]
+pub struct DeltaOf<A> {
+    /// Changes to the entries.
+    entries: A,
+    /// Changed permissions, with `None` for users whose permissions were deleted.
+    permissions: BTreeMap<PublicKey, Option<PermissionSet>>,
+    /// The new permissions for anyone, if they changed.
+    anyone: Option<PermissionSet>,
+    /// The new owner, if it changed.
+    owner: Option<PublicKey>,
+    /// The new owners, if they changed.
+    owners: Option<Owners>,
+    /// Version of the Map fields after the changes.
+    version: u64,
+    /// Revision of the Map after the changes.
+    revision: u64,
+    /// Hash of the serialised Map after the changes.
+    hash: [u8; 32],
+}
+
+impl<A> DeltaOf<A> {
+    /// Returns the changes to the entries.
+    pub fn entries(&self) -> &A {
+        &self.entries
+    }
+
+    /// Returns the changed permissions, with `None` for users whose permissions were deleted.
+    pub fn permissions(&self) -> &BTreeMap<PublicKey, Option<PermissionSet>> {
+        &self.permissions
+    }
+
+    /// Returns the new permissions for anyone, if they changed.
+    pub fn anyone_permissions(&self) -> Option<&PermissionSet> {
+        self.anyone.as_ref()
+    }
+
+    /// Returns the new owner, if it changed.
+    pub fn owner(&self) -> Option<&PublicKey> {
+        self.owner.as_ref()
+    }
+
+    /// Returns the new owners, if they changed.
+    pub fn owners(&self) -> Option<&Owners> {
+        self.owners.as_ref()
+    }
+
+    /// Returns the version of the Map fields after the changes.
+    pub fn version(&self) -> u64 {
+        self.version
+    }
+
+    /// Returns the revision of the Map after the changes.
+    pub fn revision(&self) -> u64 {
+        self.revision
+    }
+}
+
+/// Changes between two states of a sequenced Map.
+pub type SeqDelta = DeltaOf<SeqEntryActions>;
+/// Changes between two states of an unsequenced Map.
+pub type UnseqDelta = DeltaOf<UnseqEntryActions>;
+
+/// Wrapper type for changes to a Map, which can be sequenced or unsequenced.
+#[allow(clippy::large_enum_variant)]
+#[derive(Hash, Eq, PartialEq, PartialOrd, Ord, Clone, Serialize, Deserialize, Debug)]
+pub enum Delta {
+    /// Changes to a sequenced Map.
+    Seq(SeqDelta),
+    /// Changes to an unsequenced Map.
+    Unseq(UnseqDelta),
+}
+
 /// Sequenced entries (key-value pairs, with versioned values).
 pub type SeqEntries = BTreeMap<Vec<u8>, SeqValue>;
 /// Unsequenced entries (key-value pairs, without versioned values).
-pub type UnseqEntries = BTreeMap<Vec<u8>, Vec<u8>>;
+pub type UnseqEntries = BTreeMap<Vec<u8>, Bytes>;
+
+/// Range of Map keys.
+#[derive(Hash, Eq, PartialEq, PartialOrd, Ord, Clone, Serialize, Deserialize, Debug)]
+pub enum KeyRange {
+    /// Keys starting with the given prefix.
+    Prefix(Vec<u8>),
+    /// Keys from `start` (inclusive) to `end` (exclusive), or to the last key if `end` is `None`.
+    Range {
+        /// First key of the range.
+        start: Vec<u8>,
+        /// Key after the last key of the range.
+        end: Option<Vec<u8>>,
+    },
+}
+
+impl KeyRange {
+    fn start(&self) -> &[u8] {
+        match self {
+            KeyRange::Prefix(prefix) => prefix,
+            KeyRange::Range { start, .. } => start,
+        }
+    }
+
+    /// Returns true if `key` is in the range.
+    pub fn contains(&self, key: &[u8]) -> bool {
+        match self {
+            KeyRange::Prefix(prefix) => key.starts_with(prefix),
+            KeyRange::Range { start, end } => {
+                key >= &start[..] && end.as_ref().map_or(true, |end| key < &end[..])
+            }
+        }
+    }
+}
+
+/// Page of results from a key range query.
+#[derive(Hash, Eq, PartialEq, PartialOrd, Ord, Clone, Serialize, Deserialize, Debug)]
+pub struct Page<T> {
+    items: T,
+    continue_from: Option<Vec<u8>>,
+}
+
+impl<T> Page<T> {
+    /// Returns the results.
+    pub fn items(&self) -> &T {
+        &self.items
+    }
+
+    /// Returns the results, consuming the page.
+    pub fn into_items(self) -> T {
+        self.items
+    }
+
+    /// Returns the key to continue the query from, or `None` if there are no more results.
+    pub fn continue_from(&self) -> Option<&[u8]> {
+        self.continue_from.as_ref().map(|key| &key[..])
+    }
+}
+
+/// Page of Map keys.
+pub type KeysPage = Page<BTreeSet<Vec<u8>>>;
+/// Page of Map entries.
+pub type EntriesPage = Page<Entries>;
+
+// Walks the entries in order from the start of the range, so only the returned entries and the
+// key to continue from are visited.
+fn range_page<V: Clone>(
+    data: &BTreeMap<Vec<u8>, V>,
+    range: &KeyRange,
+    limit: u64,
+    continue_from: Option<&[u8]>,
+) -> Page<BTreeMap<Vec<u8>, V>> {
+    let start = match continue_from {
+        Some(key) if key > range.start() => key,
+        _ => range.start(),
+    };
+    let mut entries = data
+        .range::<[u8], _>((Bound::Included(start), Bound::Unbounded))
+        .take_while(|(key, _)| range.contains(key));
+    let items = entries
+        .by_ref()
+        .take(limit as usize)
+        .map(|(key, value)| (key.clone(), value.clone()))
+        .collect();
+    let continue_from = entries.next().map(|(key, _)| key.clone());
+    Page {
+        items,
+        continue_from,
+    }
+}
 
 /// Wrapper type for entries, which can be sequenced or unsequenced.
 #[derive(Hash, Eq, PartialEq, PartialOrd, Ord, Clone, Serialize, Deserialize, Debug)]
@@ -1164,9 +2902,23 @@ impl From<UnseqEntries> for Entries {
 
 #[cfg(test)]
 mod tests {
-    use super::{Address, XorName};
+    use super::{
+        Action, Address, Data, EntryError, Error, Guard, KeyRange, Limits, OwnerOp, Owners,
+        PermissionSet, PublicKey, SeqData, SeqEntryActions, SeqValue, SignedOwnerOp, Transaction,
+        UnseqData, UnseqEntryActions, XorName, MAX_MAP_VALUE_SIZE_IN_BYTES,
+    };
+    use crate::{utils, Keypair};
+    use bytes::Bytes;
+    use threshold_crypto::SecretKey;
     use unwrap::unwrap;
 
+    fn value(data: &[u8], version: u64) -> SeqValue {
+        SeqValue {
+            data: Bytes::copy_from_slice(data),
+            version,
+        }
+    }
+
     #[test]
     fn zbase32_encode_decode_map_address() {
         let name = XorName(rand::random());
@@ -1175,4 +2927,582 @@ mod tests {
         let decoded = unwrap!(self::Address::decode_from_zbase32(&encoded));
         assert_eq!(address, decoded);
     }
+
+    #[test]
+    fn threshold_owners() {
+        let mut rng = rand::thread_rng();
+        let keypairs: Vec<_> = (0..3).map(|_| Keypair::new_bls(&mut rng)).collect();
+        let keys: Vec<_> = keypairs.iter().map(Keypair::public_key).collect();
+        let user = PublicKey::Bls(SecretKey::random().public_key());
+
+        let mut data = UnseqData::new(XorName(rand::random()), 15000, keys[0]);
+        unwrap!(data.check_is_owner(keys[0]));
+        let owners = unwrap!(Owners::new(keys.iter().cloned().collect(), 2));
+        let mut op = SignedOwnerOp::new(
+            *data.address(),
+            OwnerOp::ChangeOwners {
+                owner: keys[0],
+                owners: owners.clone(),
+            },
+            1,
+        );
+        unwrap!(op.add_signature(keys[0], keypairs[0].sign(&op.signing_bytes())));
+        unwrap!(data.apply_owner_op(op));
+        assert_eq!(data.owners(), &owners);
+
+        // A single owner can no longer act alone.
+        match data.check_is_owner(keys[0]) {
+            Err(Error::AccessDenied) => (),
+            result => panic!("Unexpected result: {:?}", result),
+        }
+        match data.change_owner(keys[1], 2) {
+            Err(Error::InvalidOwners) => (),
+            result => panic!("Unexpected result: {:?}", result),
+        }
+        match data.set_user_permissions(user, PermissionSet::new(), 2) {
+            Err(Error::InvalidOwners) => (),
+            result => panic!("Unexpected result: {:?}", result),
+        }
+        match data.check_permissions(Action::ManagePermissions, keys[0]) {
+            Err(Error::AccessDenied) => (),
+            result => panic!("Unexpected result: {:?}", result),
+        }
+
+        let mut op = SignedOwnerOp::new(
+            *data.address(),
+            OwnerOp::SetUserPermissions {
+                user,
+                permissions: PermissionSet::new().allow(Action::Insert),
+            },
+            2,
+        );
+        unwrap!(op.add_signature(keys[1], keypairs[1].sign(&op.signing_bytes())));
+        match op.add_signature(keys[2], keypairs[1].sign(&op.signing_bytes())) {
+            Err(Error::InvalidSignature) => (),
+            result => panic!("Unexpected result: {:?}", result),
+        }
+        match data.apply_owner_op(op.clone()) {
+            Err(Error::AccessDenied) => (),
+            result => panic!("Unexpected result: {:?}", result),
+        }
+        unwrap!(op.add_signature(keys[2], keypairs[2].sign(&op.signing_bytes())));
+        unwrap!(data.apply_owner_op(op));
+        unwrap!(data.check_permissions(Action::Insert, user));
+
+        // Any owner can still edit the entries.
+        let actions = UnseqEntryActions::new().ins(b"key".to_vec(), b"value".to_vec());
+        unwrap!(data.mutate_entries(actions, keys[2]));
+
+        // Changing the owner without validation keeps the owners.
+        assert!(!data.change_owner_without_validation(user, 4));
+        assert!(data.change_owner_without_validation(keys[1], 4));
+        assert_eq!(data.owner(), &keys[1]);
+        assert_eq!(data.owners(), &owners);
+
+        match Owners::new(keys.iter().cloned().collect(), 4) {
+            Err(Error::InvalidOwners) => (),
+            result => panic!("Unexpected result: {:?}", result),
+        }
+
+        // Deserialising goes through the same checks.
+        let serialised = utils::serialise(&owners);
+        assert_eq!(unwrap!(bincode::deserialize::<Owners>(&serialised)), owners);
+        let serialised = utils::serialise(&(owners.keys(), 4usize));
+        assert!(bincode::deserialize::<Owners>(&serialised).is_err());
+    }
+
+    #[test]
+    fn convert() {
+        let owner = PublicKey::Bls(SecretKey::random().public_key());
+        let user = PublicKey::Bls(SecretKey::random().public_key());
+        let permissions = vec![(user, PermissionSet::new().allow(Action::Read))]
+            .into_iter()
+            .collect();
+        let entries = vec![(b"key".to_vec(), Bytes::from_static(b"value"))]
+            .into_iter()
+            .collect();
+        let data = Data::Unseq(unwrap!(UnseqData::new_with_data(
+            XorName(rand::random()),
+            15000,
+            entries,
+            permissions,
+            owner
+        )));
+
+        match data.convert(user) {
+            Err(Error::AccessDenied) => (),
+            result => panic!("Unexpected result: {:?}", result),
+        }
+
+        let seq = unwrap!(data.convert(owner));
+        assert!(seq.is_seq());
+        assert_eq!(seq.name(), data.name());
+        assert_eq!(seq.tag(), data.tag());
+        assert_eq!(seq.owner(), owner);
+        unwrap!(seq.check_permissions(Action::Read, user));
+        match seq {
+            Data::Seq(ref seq) => assert_eq!(seq.get(b"key"), Some(&value(b"value", 0))),
+            Data::Unseq(_) => panic!("Unexpected unsequenced Map"),
+        }
+
+        // Converting back gives the original Map.
+        assert_eq!(unwrap!(seq.convert(owner)), data);
+
+        // Maps with several owners are converted with an operation signed by enough of them.
+        let mut rng = rand::thread_rng();
+        let keypairs: Vec<_> = (0..2).map(|_| Keypair::new_bls(&mut rng)).collect();
+        let keys: Vec<_> = keypairs.iter().map(Keypair::public_key).collect();
+        let mut data = Data::Unseq(UnseqData::new(XorName(rand::random()), 15000, keys[0]));
+        let owners = unwrap!(Owners::new(keys.iter().cloned().collect(), 2));
+        let mut op = SignedOwnerOp::new(
+            *data.address(),
+            OwnerOp::ChangeOwners {
+                owner: keys[0],
+                owners,
+            },
+            1,
+        );
+        unwrap!(op.add_signature(keys[0], keypairs[0].sign(&op.signing_bytes())));
+        unwrap!(data.apply_owner_op(op));
+        match data.convert(keys[0]) {
+            Err(Error::AccessDenied) => (),
+            result => panic!("Unexpected result: {:?}", result),
+        }
+
+        let mut op = SignedOwnerOp::new(*data.address(), OwnerOp::Convert, 2);
+        unwrap!(op.add_signature(keys[0], keypairs[0].sign(&op.signing_bytes())));
+        match data.apply_owner_op(op.clone()) {
+            Err(Error::AccessDenied) => (),
+            result => panic!("Unexpected result: {:?}", result),
+        }
+        unwrap!(op.add_signature(keys[1], keypairs[1].sign(&op.signing_bytes())));
+        unwrap!(data.apply_owner_op(op.clone()));
+        assert!(data.is_seq());
+        assert_eq!(data.version(), 2);
+        assert_eq!(data.owners().threshold(), 2);
+
+        // The operation is for the address the Map had before.
+        match data.apply_owner_op(op) {
+            Err(Error::InvalidOperation) => (),
+            result => panic!("Unexpected result: {:?}", result),
+        }
+    }
+
+    #[test]
+    fn anyone_permissions() {
+        let owner = PublicKey::Bls(SecretKey::random().public_key());
+        let user = PublicKey::Bls(SecretKey::random().public_key());
+        let anyone = PublicKey::Bls(SecretKey::random().public_key());
+        let name = XorName(rand::random());
+
+        let mut data = UnseqData::new(name, 15000, owner);
+        assert!(!data.is_pub());
+        assert!(!data.is_action_allowed(&anyone, Action::Read));
+        match data.set_anyone_permissions(PermissionSet::new().allow(Action::Insert), 1) {
+            Err(Error::InvalidOperation) => (),
+            result => panic!("Unexpected result: {:?}", result),
+        }
+
+        let mut data = UnseqData::new_pub(name, 15000, owner);
+        assert!(data.is_pub());
+        assert!(data.address().is_pub());
+        assert_eq!(data.address().name(), &name);
+        assert!(data.is_action_allowed(&anyone, Action::Read));
+        assert!(!data.is_action_allowed(&anyone, Action::Insert));
+
+        unwrap!(data.set_anyone_permissions(PermissionSet::new().allow(Action::Insert), 1));
+        unwrap!(data.set_user_permissions(user, PermissionSet::new(), 2));
+        unwrap!(data.check_permissions(Action::Insert, anyone));
+        // Users' own permissions take precedence over those for anyone.
+        match data.check_permissions(Action::Insert, user) {
+            Err(Error::AccessDenied) => (),
+            result => panic!("Unexpected result: {:?}", result),
+        }
+        unwrap!(data.check_permissions(Action::Read, user));
+
+        let actions = UnseqEntryActions::new().ins(b"key".to_vec(), b"value".to_vec());
+        unwrap!(data.mutate_entries(actions, anyone));
+        let actions = UnseqEntryActions::new().del(b"key".to_vec());
+        match data.mutate_entries(actions, anyone) {
+            Err(Error::AccessDenied) => (),
+            result => panic!("Unexpected result: {:?}", result),
+        }
+
+        let entries = vec![(b"key".to_vec(), value(b"value", 0))]
+            .into_iter()
+            .collect();
+        let data = unwrap!(SeqData::new_pub_with_data(
+            name,
+            15000,
+            entries,
+            Default::default(),
+            owner
+        ));
+        assert!(data.is_pub());
+        assert!(data.is_action_allowed(&anyone, Action::Read));
+        assert_eq!(data.get(b"key"), Some(&value(b"value", 0)));
+    }
+
+    #[test]
+    fn size_limits() {
+        let owner = PublicKey::Bls(SecretKey::random().public_key());
+        let name = XorName(rand::random());
+        let limits = Limits {
+            max_entries: 2,
+            max_key_size: 4,
+            ..Default::default()
+        };
+        let mut data = unwrap!(UnseqData::new(name, 15000, owner).with_limits(limits));
+
+        let actions = UnseqEntryActions::new()
+            .ins(b"a".to_vec(), b"0".to_vec())
+            .ins(b"b".to_vec(), b"1".to_vec());
+        unwrap!(data.mutate_entries(actions, owner));
+
+        let actions = UnseqEntryActions::new().ins(b"c".to_vec(), b"2".to_vec());
+        match data.mutate_entries(actions, owner) {
+            Err(Error::TooManyEntries) => (),
+            result => panic!("Unexpected result: {:?}", result),
+        }
+
+        let actions = UnseqEntryActions::new()
+            .del(b"b".to_vec())
+            .ins(b"long key".to_vec(), b"2".to_vec());
+        match data.mutate_entries(actions, owner) {
+            Err(Error::ExceededSize) => (),
+            result => panic!("Unexpected result: {:?}", result),
+        }
+        // Failed mutations leave the Map unchanged.
+        assert_eq!(data.keys().len(), 2);
+        assert!(data.validate_size());
+
+        // Changes to the permissions are checked too.
+        let limits = Limits {
+            max_size: data.serialised_size(),
+            ..limits
+        };
+        let mut data = unwrap!(data.with_limits(limits));
+        let user = PublicKey::Bls(SecretKey::random().public_key());
+        match data.set_user_permissions(user, PermissionSet::new(), 1) {
+            Err(Error::ExceededSize) => (),
+            result => panic!("Unexpected result: {:?}", result),
+        }
+        assert!(data.permissions().is_empty());
+        assert_eq!(data.version(), 0);
+
+        let value = Bytes::from(vec![0; MAX_MAP_VALUE_SIZE_IN_BYTES as usize + 1]);
+        let entries = vec![(b"a".to_vec(), value)].into_iter().collect();
+        match UnseqData::new_with_data(name, 15000, entries, Default::default(), owner) {
+            Err(Error::ExceededSize) => (),
+            result => panic!("Unexpected result: {:?}", result),
+        }
+
+        // Limits cannot be raised over the network maximums.
+        let limits = Limits {
+            max_size: u64::MAX,
+            ..Default::default()
+        };
+        match UnseqData::new(name, 15000, owner).with_limits(limits) {
+            Err(Error::ExceededSize) => (),
+            result => panic!("Unexpected result: {:?}", result),
+        }
+    }
+
+    #[test]
+    fn guarded_transactions() {
+        let owner = PublicKey::Bls(SecretKey::random().public_key());
+        let mut data = unwrap!(SeqData::new_with_data(
+            XorName(rand::random()),
+            15000,
+            vec![
+                (b"a".to_vec(), value(b"0", 0)),
+                (b"b".to_vec(), value(b"1", 3)),
+            ]
+            .into_iter()
+            .collect(),
+            Default::default(),
+            owner,
+        ));
+        let actions = SeqEntryActions::new().update(b"a".to_vec(), b"2".to_vec(), 1);
+
+        let transaction = Transaction::new(actions.clone())
+            .guard(b"a".to_vec(), Guard::Absent)
+            .guard(b"b".to_vec(), Guard::Version(2))
+            .guard(b"c".to_vec(), Guard::value(b"1"))
+            .guard(b"d".to_vec(), Guard::Absent);
+        match data.transact(transaction, owner) {
+            Err(Error::InvalidEntryActions(errors)) => {
+                assert_eq!(
+                    errors,
+                    vec![
+                        (b"a".to_vec(), EntryError::EntryExists(0)),
+                        (b"b".to_vec(), EntryError::InvalidSuccessor(3)),
+                        (b"c".to_vec(), EntryError::NoSuchEntry),
+                    ]
+                    .into_iter()
+                    .collect()
+                );
+            }
+            result => panic!("Unexpected result: {:?}", result),
+        }
+        assert_eq!(data.get(b"a"), Some(&value(b"0", 0)));
+
+        let transaction =
+            Transaction::new(actions.clone()).guard(b"b".to_vec(), Guard::value(b"x"));
+        match data.transact(transaction, owner) {
+            Err(Error::InvalidEntryActions(errors)) => {
+                assert_eq!(errors.get(&b"b".to_vec()), Some(&EntryError::ValueMismatch));
+            }
+            result => panic!("Unexpected result: {:?}", result),
+        }
+
+        let transaction = Transaction::new(actions)
+            .guard(b"a".to_vec(), Guard::Version(0))
+            .guard(b"b".to_vec(), Guard::value(b"1"))
+            .guard(b"c".to_vec(), Guard::Absent);
+        unwrap!(data.transact(transaction, owner));
+        assert_eq!(data.get(b"a"), Some(&value(b"2", 1)));
+
+        // Guards cannot be read without permission.
+        let other = PublicKey::Bls(SecretKey::random().public_key());
+        let transaction = Transaction::default().guard(b"a".to_vec(), Guard::Absent);
+        match data.transact(transaction, other) {
+            Err(Error::AccessDenied) => (),
+            result => panic!("Unexpected result: {:?}", result),
+        }
+    }
+
+    #[test]
+    fn value_history() {
+        let owner = PublicKey::Bls(SecretKey::random().public_key());
+        let mut data = SeqData::new(XorName(rand::random()), 15000, owner).with_history(2);
+        let key = b"key".to_vec();
+
+        let actions = SeqEntryActions::new().ins(key.clone(), b"0".to_vec(), 0);
+        unwrap!(data.mutate_entries(actions, owner));
+        for version in 1..4 {
+            let actions = SeqEntryActions::new().update(key.clone(), vec![version as u8], version);
+            unwrap!(data.mutate_entries(actions, owner));
+        }
+        // Only the latest two prior values are kept.
+        assert_eq!(
+            data.value_history(&key),
+            Some(&[value(&[1], 1), value(&[2], 2)][..])
+        );
+
+        // Failed mutations leave the history unchanged.
+        let actions = SeqEntryActions::new().update(key.clone(), b"x".to_vec(), 1);
+        assert!(data.mutate_entries(actions, owner).is_err());
+        assert_eq!(data.history_len(), 2);
+
+        // History is kept for deleted keys until it is pruned.
+        let actions = SeqEntryActions::new().del(key.clone(), 4);
+        unwrap!(data.mutate_entries(actions, owner));
+        assert_eq!(
+            data.value_history(&key),
+            Some(&[value(&[2], 2), value(&[3], 3)][..])
+        );
+        data.prune_history(1);
+        assert_eq!(data.value_history(&key), Some(&[value(&[3], 3)][..]));
+        data.prune_history(0);
+        assert_eq!(data.value_history(&key), None);
+
+        // History is off by default.
+        let mut data = SeqData::new(XorName(rand::random()), 15000, owner);
+        let actions = SeqEntryActions::new().ins(key.clone(), b"0".to_vec(), 0);
+        unwrap!(data.mutate_entries(actions, owner));
+        let actions = SeqEntryActions::new().update(key.clone(), b"1".to_vec(), 1);
+        unwrap!(data.mutate_entries(actions, owner));
+        assert_eq!(data.history_limit(), 0);
+        assert_eq!(data.value_history(&key), None);
+
+        // The oldest prior values are dropped to keep the Map within its size limit.
+        let mut data = SeqData::new(XorName(rand::random()), 15000, owner).with_history(10);
+        let actions = SeqEntryActions::new().ins(key.clone(), vec![0; 100], 0);
+        unwrap!(data.mutate_entries(actions, owner));
+        let limits = Limits {
+            max_size: data.serialised_size() + 250,
+            ..Default::default()
+        };
+        let mut data = unwrap!(data.with_limits(limits));
+        for version in 1..10 {
+            let actions =
+                SeqEntryActions::new().update(key.clone(), vec![version as u8; 100], version);
+            unwrap!(data.mutate_entries(actions, owner));
+        }
+        assert_eq!(data.value_history(&key), Some(&[value(&[8; 100], 8)][..]));
+    }
+
+    #[test]
+    fn key_range_queries() {
+        let owner = PublicKey::Bls(SecretKey::random().public_key());
+        let data = unwrap!(UnseqData::new_with_data(
+            XorName(rand::random()),
+            15000,
+            vec![
+                (b"a".to_vec(), Bytes::from_static(b"0")),
+                (b"ba".to_vec(), Bytes::from_static(b"1")),
+                (b"bb".to_vec(), Bytes::from_static(b"2")),
+                (b"bc".to_vec(), Bytes::from_static(b"3")),
+                (b"c".to_vec(), Bytes::from_static(b"4")),
+            ]
+            .into_iter()
+            .collect(),
+            Default::default(),
+            owner,
+        ));
+
+        let prefix = KeyRange::Prefix(b"b".to_vec());
+        let page = data.entries_in_range(&prefix, 2, None);
+        assert_eq!(
+            page.items().keys().cloned().collect::<Vec<_>>(),
+            vec![b"ba".to_vec(), b"bb".to_vec()]
+        );
+        assert_eq!(page.continue_from(), Some(&b"bc"[..]));
+
+        let page = data.keys_in_range(&prefix, 2, page.continue_from());
+        assert_eq!(
+            page.items().iter().cloned().collect::<Vec<_>>(),
+            vec![b"bc".to_vec()]
+        );
+        assert_eq!(page.continue_from(), None);
+
+        let range = KeyRange::Range {
+            start: b"b".to_vec(),
+            end: Some(b"bc".to_vec()),
+        };
+        let page = data.keys_in_range(&range, 10, None);
+        assert_eq!(
+            page.items().iter().cloned().collect::<Vec<_>>(),
+            vec![b"ba".to_vec(), b"bb".to_vec()]
+        );
+        assert_eq!(page.continue_from(), None);
+
+        let range = KeyRange::Range {
+            start: b"bb".to_vec(),
+            end: None,
+        };
+        let page = data.keys_in_range(&range, 10, None);
+        assert_eq!(page.items().len(), 3);
+    }
+
+    #[test]
+    fn diff_and_apply_delta() {
+        let owner = PublicKey::Bls(SecretKey::random().public_key());
+        let user = PublicKey::Bls(SecretKey::random().public_key());
+        let old = unwrap!(SeqData::new_with_data(
+            XorName(rand::random()),
+            15000,
+            vec![
+                (b"kept".to_vec(), value(b"a", 0)),
+                (b"updated".to_vec(), value(b"b", 0)),
+                (b"deleted".to_vec(), value(b"c", 0)),
+            ]
+            .into_iter()
+            .collect(),
+            Default::default(),
+            owner,
+        ));
+
+        let mut new = old.clone();
+        let actions = SeqEntryActions::new()
+            .update(b"updated".to_vec(), b"b1".to_vec(), 1)
+            .del(b"deleted".to_vec(), 1)
+            .ins(b"inserted".to_vec(), b"d".to_vec(), 0);
+        unwrap!(new.mutate_entries(actions, owner));
+        // Entry changes advance the revision, but not the version of the Map fields.
+        assert_eq!(new.version(), 0);
+        assert_eq!(new.revision(), 1);
+        let actions = SeqEntryActions::new().update(b"updated".to_vec(), b"b2".to_vec(), 2);
+        unwrap!(new.mutate_entries(actions, owner));
+        unwrap!(new.set_user_permissions(user, PermissionSet::new(), 1));
+
+        let delta = unwrap!(old.diff(&new));
+        assert_eq!(delta.entries().actions().len(), 3);
+        assert_eq!(delta.permissions().len(), 1);
+        assert_eq!(delta.owner(), None);
+        assert_eq!(delta.version(), 1);
+        assert_eq!(delta.revision(), 3);
+
+        let mut synced = old.clone();
+        unwrap!(synced.apply_delta(delta.clone()));
+        assert_eq!(synced, new);
+
+        // The delta does not apply to a Map that already has the changes.
+        let mut applied = new.clone();
+        match applied.apply_delta(delta.clone()) {
+            Err(Error::InvalidEntryActions(errors)) => {
+                assert_eq!(
+                    errors.get(&b"inserted".to_vec()),
+                    Some(&EntryError::EntryExists(0))
+                );
+            }
+            result => panic!("Unexpected result: {:?}", result),
+        }
+        assert_eq!(applied, new);
+
+        // Nor to a Map with diverging fields.
+        let mut diverged = old;
+        unwrap!(diverged.set_user_permissions(owner, PermissionSet::new(), 1));
+        match diverged.apply_delta(delta) {
+            Err(Error::InvalidSuccessor(_)) => (),
+            result => panic!("Unexpected result: {:?}", result),
+        }
+    }
+
+    #[test]
+    fn changes_since() {
+        let owner = PublicKey::Bls(SecretKey::random().public_key());
+        let user = PublicKey::Bls(SecretKey::random().public_key());
+        let mut data = UnseqData::new(XorName(rand::random()), 15000, owner).with_change_log(2);
+        let actions = UnseqEntryActions::new()
+            .ins(b"updated".to_vec(), b"a".to_vec())
+            .ins(b"deleted".to_vec(), b"b".to_vec());
+        unwrap!(data.mutate_entries(actions, owner));
+        let cached = data.clone();
+
+        let actions = UnseqEntryActions::new()
+            .update(b"updated".to_vec(), b"c".to_vec())
+            .del(b"deleted".to_vec());
+        unwrap!(data.mutate_entries(actions, owner));
+        unwrap!(data.set_user_permissions(user, PermissionSet::new(), 1));
+        assert_eq!(data.revision(), 3);
+
+        let delta = unwrap!(data.changes_since(cached.revision()));
+        assert_eq!(delta.entries().actions().len(), 2);
+        assert_eq!(delta.permissions().len(), 1);
+        assert_eq!(delta.revision(), 3);
+        let mut synced = cached;
+        unwrap!(synced.apply_delta(delta));
+        assert_eq!(synced.entries(), data.entries());
+        assert_eq!(synced.permissions(), data.permissions());
+
+        assert!(unwrap!(data.changes_since(3))
+            .entries()
+            .actions()
+            .is_empty());
+        // Only the last two changes are kept.
+        match data.changes_since(0) {
+            Err(Error::NoSuchEntry) => (),
+            result => panic!("Unexpected result: {:?}", result),
+        }
+        match data.changes_since(4) {
+            Err(Error::InvalidSuccessor(3)) => (),
+            result => panic!("Unexpected result: {:?}", result),
+        }
+
+        // Logged changes are dropped to keep the Map within its size limit.
+        let data = UnseqData::new(XorName(rand::random()), 15000, owner).with_change_log(2);
+        let limits = Limits {
+            max_size: data.serialised_size() + 30,
+            ..Default::default()
+        };
+        let mut data = unwrap!(data.with_limits(limits));
+        let actions = UnseqEntryActions::new().ins(b"k".to_vec(), b"v".to_vec());
+        unwrap!(data.mutate_entries(actions, owner));
+        match data.changes_since(0) {
+            Err(Error::NoSuchEntry) => (),
+            result => panic!("Unexpected result: {:?}", result),
+        }
+    }
 }
@@ -0,0 +1,299 @@
+// Copyright 2020 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under the MIT license <LICENSE-MIT
+// https://opensource.org/licenses/MIT> or the Modified BSD license <LICENSE-BSD
+// https://opensource.org/licenses/BSD-3-Clause>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms. Please review the Licences for the
+// specific language governing permissions and limitations relating to use of the SAFE Network
+// Software.
+
+//! Client-side encryption of the entries of private Maps.
+//!
+//! Keys are encrypted deterministically, so that the same key always gives the same encrypted
+//! key and lookups still work. Values are encrypted with a random nonce, so that equal values
+//! cannot be told apart, and are bound to the encrypted key of their entry, so that they cannot be
+//! moved to another entry.
+
+use crate::{
+    Error, MapEntries as Entries, MapSeqEntryAction as SeqEntryAction,
+    MapSeqEntryActions as SeqEntryActions, MapSeqValue as SeqValue,
+    MapUnseqEntryAction as UnseqEntryAction, MapUnseqEntryActions as UnseqEntryActions,
+    MapValue as Value, MapValues as Values, Result,
+};
+use aes_gcm::{
+    aead::{generic_array::GenericArray, Aead, NewAead, Payload},
+    Aes256Gcm,
+};
+use bytes::Bytes;
+use std::{
+    collections::BTreeSet,
+    fmt::{self, Debug, Formatter},
+};
+
+/// Length of an AES-GCM nonce.
+const NONCE_LEN: usize = 12;
+
+/// Symmetric key encrypting the keys and values of Map entries.
+///
+/// Encrypted keys and values are prefixed with their nonce. Content which cannot be encrypted or
+/// decrypted gives `Err(Error::InvalidChunk)`, as with the chunks of Blobs.
+#[derive(Clone, Eq, PartialEq)]
+pub struct EntryCipher {
+    key: [u8; 32],
+}
+
+impl EntryCipher {
+    /// Creates a cipher from a symmetric key.
+    pub fn new(key: [u8; 32]) -> Self {
+        Self { key }
+    }
+
+    /// Creates a cipher from a new random symmetric key.
+    pub fn random() -> Self {
+        Self::new(rand::random())
+    }
+
+    /// Returns the symmetric key.
+    pub fn key(&self) -> &[u8; 32] {
+        &self.key
+    }
+
+    /// Encrypts an entry key. The same key always gives the same result.
+    pub fn encrypt_key(&self, key: &[u8]) -> Result<Vec<u8>> {
+        // The nonce is derived from the symmetric key and the plaintext, so it is only ever
+        // reused for the same plaintext.
+        let mut material = self.key.to_vec();
+        material.extend_from_slice(key);
+        let hash = tiny_keccak::sha3_256(&material);
+        let mut nonce = [0; NONCE_LEN];
+        nonce.copy_from_slice(&hash[..NONCE_LEN]);
+        self.encrypt(key, &[], nonce)
+    }
+
+    /// Encrypts the value of the entry with the given encrypted key, using a random nonce.
+    pub fn encrypt_value(&self, encrypted_key: &[u8], value: &[u8]) -> Result<Vec<u8>> {
+        self.encrypt(value, encrypted_key, rand::random())
+    }
+
+    /// Decrypts an entry key.
+    ///
+    /// Returns `Err(Error::InvalidChunk)` if it was not encrypted with this cipher or has been
+    /// tampered with.
+    pub fn decrypt_key(&self, encrypted_key: &[u8]) -> Result<Vec<u8>> {
+        self.decrypt(encrypted_key, &[])
+    }
+
+    /// Encrypts the keys and values of unsequenced entry actions.
+    pub fn encrypt_unseq_actions(&self, actions: UnseqEntryActions) -> Result<UnseqEntryActions> {
+        let mut encrypted = UnseqEntryActions::new();
+        for (key, action) in actions.into_actions() {
+            let key = self.encrypt_key(&key)?;
+            let action = match action {
+                UnseqEntryAction::Ins(value) => {
+                    UnseqEntryAction::Ins(self.encrypt_value(&key, &value)?.into())
+                }
+                UnseqEntryAction::Update(value) => {
+                    UnseqEntryAction::Update(self.encrypt_value(&key, &value)?.into())
+                }
+                UnseqEntryAction::Del => UnseqEntryAction::Del,
+            };
+            encrypted.add_action(key, action);
+        }
+        Ok(encrypted)
+    }
+
+    /// Encrypts the keys and values of sequenced entry actions. Versions are kept as they are.
+    pub fn encrypt_seq_actions(&self, actions: SeqEntryActions) -> Result<SeqEntryActions> {
+        let mut encrypted = SeqEntryActions::new();
+        for (key, action) in actions.into_actions() {
+            let key = self.encrypt_key(&key)?;
+            let action = match action {
+                SeqEntryAction::Ins(value) => {
+                    SeqEntryAction::Ins(self.encrypt_seq_value(&key, &value)?)
+                }
+                SeqEntryAction::Update(value) => {
+                    SeqEntryAction::Update(self.encrypt_seq_value(&key, &value)?)
+                }
+                SeqEntryAction::Del(version) => SeqEntryAction::Del(version),
+            };
+            encrypted.add_action(key, action);
+        }
+        Ok(encrypted)
+    }
+
+    /// Decrypts a list of entry keys.
+    pub fn decrypt_keys(&self, keys: &BTreeSet<Vec<u8>>) -> Result<BTreeSet<Vec<u8>>> {
+        keys.iter().map(|key| self.decrypt_key(key)).collect()
+    }
+
+    /// Decrypts the keys and values of entries.
+    pub fn decrypt_entries(&self, entries: &Entries) -> Result<Entries> {
+        Ok(match entries {
+            Entries::Seq(entries) => Entries::Seq(
+                entries
+                    .iter()
+                    .map(|(key, value)| {
+                        Ok((self.decrypt_key(key)?, self.decrypt_seq_value(key, value)?))
+                    })
+                    .collect::<Result<_>>()?,
+            ),
+            Entries::Unseq(entries) => Entries::Unseq(
+                entries
+                    .iter()
+                    .map(|(key, value)| {
+                        Ok((
+                            self.decrypt_key(key)?,
+                            Bytes::from(self.decrypt(value, key)?),
+                        ))
+                    })
+                    .collect::<Result<_>>()?,
+            ),
+        })
+    }
+
+    /// Decrypts the values of entries, as listed with `ListEntries`, in the order of their
+    /// decrypted keys.
+    ///
+    /// Values can only be decrypted along with the key of their entry, so the values of an
+    /// encrypted Map are listed with their keys rather than with `ListValues`.
+    pub fn decrypt_values(&self, entries: &Entries) -> Result<Values> {
+        Ok(match self.decrypt_entries(entries)? {
+            Entries::Seq(entries) => {
+                Values::Seq(entries.into_iter().map(|(_, value)| value).collect())
+            }
+            Entries::Unseq(entries) => {
+                Values::Unseq(entries.into_iter().map(|(_, value)| value).collect())
+            }
+        })
+    }
+
+    /// Decrypts the value of the entry with the given encrypted key.
+    pub fn decrypt_value(&self, encrypted_key: &[u8], value: &Value) -> Result<Value> {
+        Ok(match value {
+            Value::Seq(value) => Value::Seq(self.decrypt_seq_value(encrypted_key, value)?),
+            Value::Unseq(value) => Value::Unseq(self.decrypt(value, encrypted_key)?.into()),
+        })
+    }
+
+    fn encrypt_seq_value(&self, encrypted_key: &[u8], value: &SeqValue) -> Result<SeqValue> {
+        Ok(SeqValue {
+            data: self.encrypt_value(encrypted_key, &value.data)?.into(),
+            version: value.version,
+        })
+    }
+
+    fn decrypt_seq_value(&self, encrypted_key: &[u8], value: &SeqValue) -> Result<SeqValue> {
+        Ok(SeqValue {
+            data: self.decrypt(&value.data, encrypted_key)?.into(),
+            version: value.version,
+        })
+    }
+
+    // Encrypts `content`, authenticating `aad` along with it.
+    fn encrypt(&self, content: &[u8], aad: &[u8], nonce: [u8; NONCE_LEN]) -> Result<Vec<u8>> {
+        let mut encrypted = nonce.to_vec();
+        encrypted.extend(
+            self.cipher()
+                .encrypt(
+                    GenericArray::from_slice(&nonce),
+                    Payload { msg: content, aad },
+                )
+                .map_err(|_| Error::InvalidChunk)?,
+        );
+        Ok(encrypted)
+    }
+
+    fn decrypt(&self, encrypted: &[u8], aad: &[u8]) -> Result<Vec<u8>> {
+        if encrypted.len() < NONCE_LEN {
+            return Err(Error::InvalidChunk);
+        }
+        let (nonce, content) = encrypted.split_at(NONCE_LEN);
+        self.cipher()
+            .decrypt(
+                GenericArray::from_slice(nonce),
+                Payload { msg: content, aad },
+            )
+            .map_err(|_| Error::InvalidChunk)
+    }
+
+    fn cipher(&self) -> Aes256Gcm {
+        Aes256Gcm::new(GenericArray::from_slice(&self.key))
+    }
+}
+
+// The key is never printed.
+impl Debug for EntryCipher {
+    fn fmt(&self, formatter: &mut Formatter) -> fmt::Result {
+        write!(formatter, "MapEntryCipher(..)")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::EntryCipher;
+    use crate::{
+        Error, MapEntries, MapUnseqEntryActions, MapValue, MapValues, PublicKey, UnseqMap, XorName,
+    };
+    use bytes::Bytes;
+    use threshold_crypto::SecretKey;
+    use unwrap::unwrap;
+
+    #[test]
+    fn encrypted_entries_round_trip() {
+        let cipher = EntryCipher::random();
+        let owner = PublicKey::Bls(SecretKey::random().public_key());
+        let mut data = UnseqMap::new(XorName(rand::random()), 15000, owner);
+
+        let actions = MapUnseqEntryActions::new()
+            .ins(b"key".to_vec(), b"value".to_vec())
+            .ins(b"other".to_vec(), b"value".to_vec());
+        unwrap!(data.mutate_entries(unwrap!(cipher.encrypt_unseq_actions(actions)), owner));
+
+        // Keys are deterministic, so the entry can be looked up.
+        let key = unwrap!(cipher.encrypt_key(b"key"));
+        assert_eq!(key, unwrap!(cipher.encrypt_key(b"key")));
+        let encrypted = MapValue::Unseq(unwrap!(data.get(&key)).clone());
+        assert_ne!(encrypted, MapValue::Unseq(Bytes::from_static(b"value")));
+        assert_eq!(
+            unwrap!(cipher.decrypt_value(&key, &encrypted)),
+            MapValue::Unseq(Bytes::from_static(b"value"))
+        );
+
+        // Values are not, so equal values cannot be told apart.
+        let other = unwrap!(cipher.encrypt_key(b"other"));
+        let other_encrypted = MapValue::Unseq(unwrap!(data.get(&other)).clone());
+        assert_ne!(encrypted, other_encrypted);
+
+        // Values are bound to their key, so they cannot be swapped.
+        match cipher.decrypt_value(&other, &encrypted) {
+            Err(Error::InvalidChunk) => (),
+            result => panic!("Unexpected result: {:?}", result),
+        }
+
+        let entries = MapEntries::Unseq(data.entries().clone());
+        match unwrap!(cipher.decrypt_values(&entries)) {
+            MapValues::Unseq(values) => assert_eq!(
+                values,
+                vec![Bytes::from_static(b"value"), Bytes::from_static(b"value")]
+            ),
+            MapValues::Seq(_) => panic!("Unexpected sequenced values"),
+        }
+
+        let entries = unwrap!(cipher.decrypt_entries(&entries));
+        match entries {
+            MapEntries::Unseq(entries) => {
+                assert_eq!(
+                    entries.get(&b"key".to_vec()),
+                    Some(&Bytes::from_static(b"value"))
+                );
+                assert_eq!(entries.len(), 2);
+            }
+            MapEntries::Seq(_) => panic!("Unexpected sequenced entries"),
+        }
+
+        match EntryCipher::random().decrypt_key(&key) {
+            Err(Error::InvalidChunk) => (),
+            result => panic!("Unexpected result: {:?}", result),
+        }
+    }
+}
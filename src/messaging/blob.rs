@@ -17,6 +17,15 @@ use std::fmt;
 pub enum BlobRead {
     /// TODO: docs
     Get(BlobAddress),
+    /// Get `len` bytes of the value of a Blob, starting at `offset`.
+    GetRange {
+        /// Blob address.
+        address: BlobAddress,
+        /// Offset of the first byte to get.
+        offset: u64,
+        /// Number of bytes to get.
+        len: u64,
+    },
 }
 
 /// TODO: docs
@@ -42,15 +51,27 @@ impl BlobRead {
     /// Creates a Response containing an error, with the Response variant corresponding to the
     /// Request variant.
     pub fn error(&self, error: Error) -> QueryResponse {
-        QueryResponse::GetBlob(Err(error))
+        use BlobRead::*;
+        match self {
+            Get(_) => QueryResponse::GetBlob(Err(error)),
+            GetRange { .. } => QueryResponse::GetBlobRange(Err(error)),
+        }
     }
 
     /// Returns the type of authorisation needed for the request.
     pub fn authorisation_kind(&self) -> AuthorisationKind {
         use BlobRead::*;
         match self {
-            Get(BlobAddress::Public(_)) => AuthorisationKind::Data(DataAuthKind::PublicRead),
-            Get(BlobAddress::Private(_)) => AuthorisationKind::Data(DataAuthKind::PrivateRead),
+            Get(BlobAddress::Public(_))
+            | GetRange {
+                address: BlobAddress::Public(_),
+                ..
+            } => AuthorisationKind::Data(DataAuthKind::PublicRead),
+            Get(BlobAddress::Private(_))
+            | GetRange {
+                address: BlobAddress::Private(_),
+                ..
+            } => AuthorisationKind::Data(DataAuthKind::PrivateRead),
         }
     }
 
@@ -58,7 +79,7 @@ impl BlobRead {
     pub fn dst_address(&self) -> XorName {
         use BlobRead::*;
         match self {
-            Get(ref address) => *address.name(),
+            Get(ref address) | GetRange { ref address, .. } => *address.name(),
         }
     }
 }
@@ -90,6 +111,15 @@ impl fmt::Debug for BlobRead {
         use BlobRead::*;
         match self {
             Get(req) => write!(formatter, "{:?}", req),
+            GetRange {
+                address,
+                offset,
+                len,
+            } => write!(
+                formatter,
+                "GetRange {{ {:?}, offset: {}, len: {} }}",
+                address, offset, len
+            ),
         }
     }
 }
@@ -0,0 +1,133 @@
+// Copyright 2020 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under the MIT license <LICENSE-MIT
+// https://opensource.org/licenses/MIT> or the Modified BSD license <LICENSE-BSD
+// https://opensource.org/licenses/BSD-3-Clause>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms. Please review the Licences for the
+// specific language governing permissions and limitations relating to use of the SAFE Network
+// Software.
+
+use super::{AuthorisationKind, CmdError, DataAuthKind, QueryResponse};
+use crate::{
+    CrdtMap, CrdtMapAddress as Address, CrdtMapWriteOp as WriteOp, Error, Result, XorName,
+};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// TODO: docs
+#[derive(Hash, Eq, PartialEq, PartialOrd, Clone, Serialize, Deserialize)]
+pub enum CrdtMapRead {
+    /// Get CRDT Map.
+    Get(Address),
+    /// Get the concurrently written values of a key.
+    GetValues {
+        /// CRDT Map address.
+        address: Address,
+        /// Key to get.
+        key: Vec<u8>,
+    },
+    /// List CRDT Map entries.
+    ListEntries(Address),
+}
+
+/// TODO: docs
+#[allow(clippy::large_enum_variant)]
+#[derive(Hash, Eq, PartialEq, PartialOrd, Clone, Serialize, Deserialize)]
+pub enum CrdtMapWrite {
+    /// Create a new CRDT Map.
+    New(CrdtMap),
+    /// Edit the entries. The operation can be applied in any order relative to other edits.
+    Edit(WriteOp),
+    /// Delete instance.
+    Delete(Address),
+}
+
+impl CrdtMapRead {
+    /// Creates a Response containing an error, with the Response variant corresponding to the
+    /// Request variant.
+    pub fn error(&self, error: Error) -> QueryResponse {
+        use CrdtMapRead::*;
+        match *self {
+            Get(_) => QueryResponse::GetCrdtMap(Err(error)),
+            GetValues { .. } => QueryResponse::GetCrdtMapValues(Err(error)),
+            ListEntries(_) => QueryResponse::ListCrdtMapEntries(Err(error)),
+        }
+    }
+
+    /// Returns the type of authorisation needed for the request.
+    pub fn authorisation_kind(&self) -> AuthorisationKind {
+        AuthorisationKind::Data(DataAuthKind::PrivateRead)
+    }
+
+    /// Returns the address of the destination for request.
+    pub fn dst_address(&self) -> XorName {
+        use CrdtMapRead::*;
+        match self {
+            Get(ref address) | GetValues { ref address, .. } | ListEntries(ref address) => {
+                *address.name()
+            }
+        }
+    }
+}
+
+impl fmt::Debug for CrdtMapRead {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        use CrdtMapRead::*;
+        write!(
+            formatter,
+            "Request::{}",
+            match *self {
+                Get(_) => "GetCrdtMap",
+                GetValues { .. } => "GetCrdtMapValues",
+                ListEntries(_) => "ListCrdtMapEntries",
+            }
+        )
+    }
+}
+
+impl CrdtMapWrite {
+    /// Checks that the request is valid to send, i.e. that a new CRDT Map is within its size
+    /// limits.
+    pub fn validate(&self) -> Result<()> {
+        match self {
+            CrdtMapWrite::New(data) => data.check_size(),
+            _ => Ok(()),
+        }
+    }
+
+    /// Creates a Response containing an error, with the Response variant corresponding to the
+    /// Request variant.
+    pub fn error(&self, error: Error) -> CmdError {
+        CmdError::Data(error)
+    }
+
+    /// Returns the type of authorisation needed for the request.
+    pub fn authorisation_kind(&self) -> AuthorisationKind {
+        AuthorisationKind::Data(DataAuthKind::Write)
+    }
+
+    /// Returns the address of the destination for request.
+    pub fn dst_address(&self) -> XorName {
+        use CrdtMapWrite::*;
+        match self {
+            New(ref data) => *data.name(),
+            Edit(ref op) => *op.address.name(),
+            Delete(ref address) => *address.name(),
+        }
+    }
+}
+
+impl fmt::Debug for CrdtMapWrite {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        use CrdtMapWrite::*;
+        write!(
+            formatter,
+            "Request::{}",
+            match *self {
+                New(_) => "NewCrdtMap",
+                Edit(_) => "EditCrdtMap",
+                Delete(_) => "DeleteCrdtMap",
+            }
+        )
+    }
+}
@@ -10,6 +10,7 @@
 use super::{
     account::{AccountRead, AccountWrite},
     blob::{BlobRead, BlobWrite},
+    crdt_map::{CrdtMapRead, CrdtMapWrite},
     map::{MapRead, MapWrite},
     sequence::{SequenceRead, SequenceWrite},
     AuthorisationKind, CmdError, QueryResponse,
@@ -31,6 +32,8 @@ pub enum DataCmd {
     /// Use this only while we don't
     /// have Authenticator as its own app.
     Account(AccountWrite), // <- "LoginPacket"
+    /// TODO: docs
+    CrdtMap(CrdtMapWrite),
 }
 
 impl DataCmd {
@@ -43,6 +46,7 @@ impl DataCmd {
             Map(c) => c.error(error),
             Sequence(c) => c.error(error),
             Account(c) => c.error(error),
+            CrdtMap(c) => c.error(error),
         }
     }
     /// Returns the type of authorisation needed for the cuest.
@@ -53,6 +57,7 @@ impl DataCmd {
             Map(c) => c.authorisation_kind(),
             Sequence(c) => c.authorisation_kind(),
             Account(c) => c.authorisation_kind(),
+            CrdtMap(c) => c.authorisation_kind(),
         }
     }
 
@@ -64,6 +69,7 @@ impl DataCmd {
             Map(c) => c.dst_address(),
             Sequence(c) => c.dst_address(),
             Account(c) => c.dst_address(),
+            CrdtMap(c) => c.dst_address(),
         }
     }
 }
@@ -76,6 +82,7 @@ impl fmt::Debug for DataCmd {
             Map(c) => write!(formatter, "{:?}", c),
             Sequence(c) => write!(formatter, "{:?}", c),
             Account(c) => write!(formatter, "{:?}", c),
+            CrdtMap(c) => write!(formatter, "{:?}", c),
         }
     }
 }
@@ -93,6 +100,8 @@ pub enum DataQuery {
     /// Use this only while we don't
     /// have Authenticator as its own app.
     Account(AccountRead), // <- "LoginPacket"
+    /// TODO: docs
+    CrdtMap(CrdtMapRead),
 }
 
 impl DataQuery {
@@ -105,6 +114,7 @@ impl DataQuery {
             Map(q) => q.error(error),
             Sequence(q) => q.error(error),
             Account(q) => q.error(error),
+            CrdtMap(q) => q.error(error),
         }
     }
 
@@ -116,6 +126,7 @@ impl DataQuery {
             Map(q) => q.authorisation_kind(),
             Sequence(q) => q.authorisation_kind(),
             Account(q) => q.authorisation_kind(),
+            CrdtMap(q) => q.authorisation_kind(),
         }
     }
 
@@ -127,6 +138,7 @@ impl DataQuery {
             Map(q) => q.dst_address(),
             Sequence(q) => q.dst_address(),
             Account(q) => q.dst_address(),
+            CrdtMap(q) => q.dst_address(),
         }
     }
 }
@@ -139,6 +151,7 @@ impl fmt::Debug for DataQuery {
             Map(q) => write!(formatter, "{:?}", q),
             Sequence(q) => write!(formatter, "{:?}", q),
             Account(q) => write!(formatter, "{:?}", q),
+            CrdtMap(q) => write!(formatter, "{:?}", q),
         }
     }
 }
@@ -9,8 +9,9 @@
 
 use super::{AuthorisationKind, CmdError, DataAuthKind, QueryResponse};
 use crate::{
-    Error, Map, MapAddress as Address, MapEntryActions as Changes,
-    MapPermissionSet as PermissionSet, PublicKey, XorName,
+    Error, Map, MapAddress as Address, MapEntryActions as Changes, MapKeyRange as KeyRange,
+    MapPermissionSet as PermissionSet, MapSignedOwnerOp as SignedOwnerOp,
+    MapTransaction as Transaction, PublicKey, Result, XorName,
 };
 use serde::{Deserialize, Serialize};
 use std::fmt;
@@ -46,6 +47,44 @@ pub enum MapRead {
         /// User to get permissions for.
         user: PublicKey,
     },
+    /// Get the changes to a Map since the given revision, as held by the client. Vaults compute
+    /// them from the changes the Map logs, and fail with `NoSuchEntry` if they are no longer
+    /// logged, in which case the whole Map has to be fetched.
+    GetChangesSince {
+        /// Map address.
+        address: Address,
+        /// Revision of the Map held by the client.
+        revision: u64,
+    },
+    /// List Map entries with keys in a range, a page at a time.
+    ListEntriesInRange {
+        /// Map address.
+        address: Address,
+        /// Range of keys to list.
+        range: KeyRange,
+        /// Maximum number of entries to return.
+        limit: u64,
+        /// Key to continue from, as returned with the previous page.
+        continue_from: Option<Vec<u8>>,
+    },
+    /// List Map keys in a range, a page at a time.
+    ListKeysInRange {
+        /// Map address.
+        address: Address,
+        /// Range of keys to list.
+        range: KeyRange,
+        /// Maximum number of keys to return.
+        limit: u64,
+        /// Key to continue from, as returned with the previous page.
+        continue_from: Option<Vec<u8>>,
+    },
+    /// Get the prior values of a key of a sequenced Map, oldest first.
+    GetValueHistory {
+        /// Map address.
+        address: Address,
+        /// Key to get the history of.
+        key: Vec<u8>,
+    },
 }
 
 /// TODO: docs
@@ -83,6 +122,29 @@ pub enum MapWrite {
         /// Version to set.
         version: u64,
     },
+    /// Edit entries of a sequenced Map if the transaction guards hold.
+    Transact {
+        /// Map address.
+        address: Address,
+        /// Transaction to apply.
+        transaction: Transaction,
+    },
+    /// Apply an owner-level operation signed by enough owners.
+    ApplyOwnerOp(SignedOwnerOp),
+    /// Set the permissions of anyone without their own permissions on a public Map.
+    SetAnyonePermissions {
+        /// Map address.
+        address: Address,
+        /// New permissions.
+        permissions: PermissionSet,
+        /// Version to set.
+        version: u64,
+    },
+    /// Convert a sequenced Map into an unsequenced one, or the other way round. Only an owner
+    /// who can act alone can convert a Map this way, and the converted Map has a new address of
+    /// the other kind. Maps with several owners are converted with `ApplyOwnerOp` and an
+    /// `OwnerOp::Convert`.
+    Convert(Address),
 }
 
 impl MapRead {
@@ -93,13 +155,17 @@ impl MapRead {
         match *self {
             Get(_) => QueryResponse::GetMap(Err(error)),
             GetValue { .. } => QueryResponse::GetMapValue(Err(error)),
+            GetValueHistory { .. } => QueryResponse::GetMapValueHistory(Err(error)),
             GetShell(_) => QueryResponse::GetMapShell(Err(error)),
             GetVersion(_) => QueryResponse::GetMapVersion(Err(error)),
             ListEntries(_) => QueryResponse::ListMapEntries(Err(error)),
             ListKeys(_) => QueryResponse::ListMapKeys(Err(error)),
             ListValues(_) => QueryResponse::ListMapValues(Err(error)),
+            ListEntriesInRange { .. } => QueryResponse::ListMapEntriesInRange(Err(error)),
+            ListKeysInRange { .. } => QueryResponse::ListMapKeysInRange(Err(error)),
             ListPermissions(_) => QueryResponse::ListMapPermissions(Err(error)),
             ListUserPermissions { .. } => QueryResponse::ListMapUserPermissions(Err(error)),
+            GetChangesSince { .. } => QueryResponse::GetMapChanges(Err(error)),
         }
     }
 
@@ -107,15 +173,25 @@ impl MapRead {
     pub fn authorisation_kind(&self) -> AuthorisationKind {
         use MapRead::*;
         match *self {
-            Get(_)
-            | GetValue { .. }
-            | GetShell(_)
-            | GetVersion(_)
-            | ListEntries(_)
-            | ListKeys(_)
-            | ListValues(_)
-            | ListPermissions(_)
-            | ListUserPermissions { .. } => AuthorisationKind::Data(DataAuthKind::PrivateRead),
+            Get(address)
+            | GetValue { address, .. }
+            | GetValueHistory { address, .. }
+            | GetShell(address)
+            | GetVersion(address)
+            | ListEntries(address)
+            | ListKeys(address)
+            | ListValues(address)
+            | ListEntriesInRange { address, .. }
+            | ListKeysInRange { address, .. }
+            | ListPermissions(address)
+            | ListUserPermissions { address, .. }
+            | GetChangesSince { address, .. } => {
+                if address.is_pub() {
+                    AuthorisationKind::Data(DataAuthKind::PublicRead)
+                } else {
+                    AuthorisationKind::Data(DataAuthKind::PrivateRead)
+                }
+            }
         }
     }
 
@@ -125,13 +201,17 @@ impl MapRead {
         match self {
             Get(ref address)
             | GetValue { ref address, .. }
+            | GetValueHistory { ref address, .. }
             | GetShell(ref address)
             | GetVersion(ref address)
             | ListEntries(ref address)
             | ListKeys(ref address)
             | ListValues(ref address)
+            | ListEntriesInRange { ref address, .. }
+            | ListKeysInRange { ref address, .. }
             | ListPermissions(ref address)
-            | ListUserPermissions { ref address, .. } => *address.name(),
+            | ListUserPermissions { ref address, .. }
+            | GetChangesSince { ref address, .. } => *address.name(),
         }
     }
 }
@@ -145,19 +225,33 @@ impl fmt::Debug for MapRead {
             match *self {
                 Get(_) => "GetMap",
                 GetValue { .. } => "GetMapValue",
+                GetValueHistory { .. } => "GetMapValueHistory",
                 GetShell(_) => "GetMapShell",
                 GetVersion(_) => "GetMapVersion",
                 ListEntries(_) => "ListMapEntries",
                 ListKeys(_) => "ListMapKeys",
                 ListValues(_) => "ListMapValues",
+                ListEntriesInRange { .. } => "ListMapEntriesInRange",
+                ListKeysInRange { .. } => "ListMapKeysInRange",
                 ListPermissions(_) => "ListMapPermissions",
                 ListUserPermissions { .. } => "ListMapUserPermissions",
+                GetChangesSince { .. } => "GetMapChanges",
             }
         )
     }
 }
 
 impl MapWrite {
+    /// Checks that the request is valid to send, i.e. that a new Map is within its size limits
+    /// and that a deleted Map is not public.
+    pub fn validate(&self) -> Result<()> {
+        match self {
+            MapWrite::New(data) => data.check_size(),
+            MapWrite::Delete(address) if address.is_pub() => Err(Error::InvalidOperation),
+            _ => Ok(()),
+        }
+    }
+
     /// Creates a Response containing an error, with the Response variant corresponding to the
     /// Request variant.
     pub fn error(&self, error: Error) -> CmdError {
@@ -174,10 +268,14 @@ impl MapWrite {
         use MapWrite::*;
         match self {
             New(ref data) => *data.name(),
+            ApplyOwnerOp(ref op) => *op.address().name(),
             Delete(ref address)
+            | Convert(ref address)
             | SetUserPermissions { ref address, .. }
+            | SetAnyonePermissions { ref address, .. }
             | DelUserPermissions { ref address, .. }
-            | Edit { ref address, .. } => *address.name(),
+            | Edit { ref address, .. }
+            | Transact { ref address, .. } => *address.name(),
         }
     }
 }
@@ -191,9 +289,13 @@ impl fmt::Debug for MapWrite {
             match *self {
                 New(_) => "NewMap",
                 Delete(_) => "DeleteMap",
+                Convert(_) => "ConvertMap",
                 SetUserPermissions { .. } => "SetMapUserPermissions",
+                SetAnyonePermissions { .. } => "SetMapAnyonePermissions",
+                ApplyOwnerOp(_) => "ApplyMapOwnerOp",
                 DelUserPermissions { .. } => "DelMapUserPermissions",
                 Edit { .. } => "EditMap",
+                Transact { .. } => "TransactMap",
             }
         )
     }
@@ -11,6 +11,7 @@ mod account;
 mod auth;
 mod blob;
 mod cmd;
+mod crdt_map;
 mod data;
 mod duty;
 mod map;
@@ -24,6 +25,7 @@ pub use self::{
     auth::{AuthCmd, AuthQuery},
     blob::{BlobRead, BlobWrite},
     cmd::Cmd,
+    crdt_map::{CrdtMapRead, CrdtMapWrite},
     data::{DataCmd, DataQuery},
     duty::{AdultDuties, Duty, ElderDuties, NodeDuties},
     map::{MapRead, MapWrite},
@@ -33,10 +35,11 @@ pub use self::{
     transfer::{TransferCmd, TransferQuery},
 };
 use crate::{
-    errors::ErrorDebug, utils, AppPermissions, Blob, BlsProof, DebitAgreementProof, Error, Map,
-    MapEntries, MapPermissionSet, MapValue, MapValues, Money, Proof, PublicKey, ReplicaEvent,
-    ReplicaPublicKeySet, Result, Sequence, SequenceEntries, SequenceEntry, SequenceOwner,
-    SequencePermissions, SequenceUserPermissions, Signature, TransferValidated,
+    errors::ErrorDebug, utils, AppPermissions, Blob, BlsProof, CrdtMap, CrdtMapEntries,
+    CrdtMapValues, DebitAgreementProof, Error, Map, MapDelta, MapEntries, MapEntriesPage,
+    MapKeysPage, MapPermissionSet, MapSeqValue, MapValue, MapValues, Money, Proof, PublicKey,
+    ReplicaEvent, ReplicaPublicKeySet, Result, Sequence, SequenceEntries, SequenceEntry,
+    SequenceOwner, SequencePermissions, SequenceUserPermissions, Signature, TransferValidated,
 };
 use serde::{Deserialize, Serialize};
 use std::{
@@ -491,6 +494,34 @@ pub enum QueryResponse {
     //
     /// Get a list of authorised keys and the version of the auth keys container from Elders.
     ListAuthKeysAndVersion(Result<(BTreeMap<PublicKey, AppPermissions>, u64)>),
+    //
+    // ===== Blob =====
+    //
+    /// Get a range of the value of a Blob.
+    GetBlobRange(Result<Vec<u8>>),
+    //
+    // ===== Map =====
+    //
+    /// Get Map changes since a revision.
+    GetMapChanges(Result<MapDelta>),
+    //
+    // ===== CRDT Map =====
+    //
+    /// Get CRDT Map.
+    GetCrdtMap(Result<CrdtMap>),
+    /// Get the concurrently written values of a CRDT Map key.
+    GetCrdtMapValues(Result<CrdtMapValues>),
+    /// List all CRDT Map entries.
+    ListCrdtMapEntries(Result<CrdtMapEntries>),
+    //
+    // ===== Map =====
+    //
+    /// List a page of Map entries with keys in a range.
+    ListMapEntriesInRange(Result<MapEntriesPage>),
+    /// List a page of Map keys in a range.
+    ListMapKeysInRange(Result<MapKeysPage>),
+    /// Get the prior values of a sequenced Map key.
+    GetMapValueHistory(Result<Vec<MapSeqValue>>),
 }
 
 /// The kind of authorisation needed for a request.
@@ -563,14 +594,22 @@ macro_rules! try_from {
 }
 
 try_from!(Blob, GetBlob);
+try_from!(Vec<u8>, GetBlobRange);
 try_from!(Map, GetMap, GetMapShell);
 try_from!(u64, GetMapVersion);
 try_from!(MapEntries, ListMapEntries);
 try_from!(BTreeSet<Vec<u8>>, ListMapKeys);
 try_from!(MapValues, ListMapValues);
+try_from!(MapEntriesPage, ListMapEntriesInRange);
+try_from!(MapKeysPage, ListMapKeysInRange);
 try_from!(MapPermissionSet, ListMapUserPermissions);
 try_from!(BTreeMap<PublicKey, MapPermissionSet>, ListMapPermissions);
 try_from!(MapValue, GetMapValue);
+try_from!(Vec<MapSeqValue>, GetMapValueHistory);
+try_from!(MapDelta, GetMapChanges);
+try_from!(CrdtMap, GetCrdtMap);
+try_from!(CrdtMapValues, GetCrdtMapValues);
+try_from!(CrdtMapEntries, ListCrdtMapEntries);
 try_from!(Sequence, GetSequence);
 try_from!(SequenceOwner, GetSequenceOwner);
 try_from!(SequenceEntries, GetSequenceRange);
@@ -593,6 +632,9 @@ impl fmt::Debug for QueryResponse {
         match self {
             // Blob
             GetBlob(res) => write!(f, "QueryResponse::GetBlob({:?})", ErrorDebug(res)),
+            GetBlobRange(res) => {
+                write!(f, "QueryResponse::GetBlobRange({:?})", ErrorDebug(res))
+            }
             // Map
             GetMap(res) => write!(f, "QueryResponse::GetMap({:?})", ErrorDebug(res)),
             GetMapShell(res) => write!(f, "QueryResponse::GetMapShell({:?})", ErrorDebug(res)),
@@ -602,6 +644,18 @@ impl fmt::Debug for QueryResponse {
             }
             ListMapKeys(res) => write!(f, "QueryResponse::ListMapKeys({:?})", ErrorDebug(res)),
             ListMapValues(res) => write!(f, "QueryResponse::ListMapValues({:?})", ErrorDebug(res)),
+            ListMapEntriesInRange(res) => write!(
+                f,
+                "QueryResponse::ListMapEntriesInRange({:?})",
+                ErrorDebug(res)
+            ),
+            ListMapKeysInRange(res) => {
+                write!(
+                    f,
+                    "QueryResponse::ListMapKeysInRange({:?})",
+                    ErrorDebug(res)
+                )
+            }
             ListMapPermissions(res) => write!(
                 f,
                 "QueryResponse::ListMapPermissions({:?})",
@@ -613,6 +667,26 @@ impl fmt::Debug for QueryResponse {
                 ErrorDebug(res)
             ),
             GetMapValue(res) => write!(f, "QueryResponse::GetMapValue({:?})", ErrorDebug(res)),
+            GetMapValueHistory(res) => {
+                write!(
+                    f,
+                    "QueryResponse::GetMapValueHistory({:?})",
+                    ErrorDebug(res)
+                )
+            }
+            GetMapChanges(res) => write!(f, "QueryResponse::GetMapChanges({:?})", ErrorDebug(res)),
+            // CRDT Map
+            GetCrdtMap(res) => write!(f, "QueryResponse::GetCrdtMap({:?})", ErrorDebug(res)),
+            GetCrdtMapValues(res) => {
+                write!(f, "QueryResponse::GetCrdtMapValues({:?})", ErrorDebug(res))
+            }
+            ListCrdtMapEntries(res) => {
+                write!(
+                    f,
+                    "QueryResponse::ListCrdtMapEntries({:?})",
+                    ErrorDebug(res)
+                )
+            }
             // Sequence
             GetSequence(res) => write!(f, "QueryResponse::GetSequence({:?})", ErrorDebug(res)),
             GetSequenceRange(res) => {
@@ -682,21 +756,80 @@ mod tests {
             TryFromError::Response(e.clone()),
             unwrap_err!(Blob::try_from(GetBlob(Err(e.clone()))))
         );
+        assert_eq!(
+            vec![3, 1],
+            unwrap!(Vec::<u8>::try_from(GetBlobRange(Ok(vec![3, 1]))))
+        );
+        assert_eq!(
+            TryFromError::WrongType,
+            unwrap_err!(Vec::<u8>::try_from(GetBlob(Ok(i_data.clone()))))
+        );
 
         let mut data = BTreeMap::new();
-        let _ = data.insert(vec![1], vec![10]);
+        let _ = data.insert(vec![1], vec![10].into());
         let owners = PublicKey::Bls(threshold_crypto::SecretKey::random().public_key());
-        let m_data = Map::Unseq(UnseqMap::new_with_data(
+        let m_data = Map::Unseq(unwrap!(UnseqMap::new_with_data(
             *i_data.name(),
             1,
             data,
             BTreeMap::new(),
             owners,
-        ));
+        )));
         assert_eq!(m_data, unwrap!(GetMap(Ok(m_data.clone())).try_into()));
         assert_eq!(
             TryFromError::Response(e.clone()),
             unwrap_err!(Map::try_from(GetMap(Err(e))))
         );
     }
+
+    #[test]
+    fn public_map_cannot_be_deleted() {
+        use crate::{Error, MapAddress};
+
+        let name = XorName::random();
+        let delete = MapWrite::Delete(MapAddress::Unseq { name, tag: 1 });
+        unwrap!(delete.validate());
+        let delete = MapWrite::Delete(MapAddress::PublicUnseq { name, tag: 1 });
+        assert_eq!(delete.validate(), Err(Error::InvalidOperation));
+    }
+
+    #[test]
+    fn wallet_update_is_signed_by_node() {
+        use crate::NodeKeypairs;
+
+        let node = NodeKeypairs::new(&mut rand::thread_rng());
+        let wallet = PublicKey::Bls(threshold_crypto::SecretKey::random().public_key());
+        let section = XorName::random();
+        let update = NodeSystemCmd::UpdateWallet {
+            wallet,
+            section,
+            version: 1,
+            proof: node.ed25519_proof(NodeSystemCmd::wallet_update_bytes(&wallet, &section, 1)),
+        };
+        assert_eq!(
+            unwrap!(update.verify_wallet_update(0)),
+            *node.public_id().name()
+        );
+        // An update cannot be replayed once a newer one is registered.
+        assert_eq!(
+            update.verify_wallet_update(1),
+            Err(Error::InvalidSuccessor(1))
+        );
+
+        let forged = NodeSystemCmd::UpdateWallet {
+            wallet,
+            section: XorName::random(),
+            version: 1,
+            proof: node.ed25519_proof(NodeSystemCmd::wallet_update_bytes(&wallet, &section, 1)),
+        };
+        assert_eq!(forged.verify_wallet_update(0), Err(Error::InvalidSignature));
+        match forged.check_section(|name| *name == section) {
+            Err(NodeCmdError::System(NodeSystemError::WalletRegistration {
+                error: Error::NoSuchSection,
+                ..
+            })) => (),
+            result => panic!("Unexpected result: {:?}", result),
+        }
+        unwrap!(update.check_section(|name| *name == section));
+    }
 }
@@ -8,8 +8,9 @@
 // Software.
 
 use crate::{
-    AccountId, Address, Blob, BlobAddress, DebitAgreementProof, Error, PublicKey, ReplicaEvent,
-    Result, Signature, SignedTransfer, TransferId, TransferValidated, XorName,
+    utils, AccountId, Address, Blob, BlobAddress, BlobShard, BlobShardAddress, DebitAgreementProof,
+    Ed25519Proof, Error, Proven, PublicKey, RelocatedRewards, ReplicaEvent, Result, Signature,
+    SignedTransfer, TransferId, TransferValidated, XorName,
 };
 use serde::{Deserialize, Serialize};
 use std::collections::BTreeSet;
@@ -38,6 +39,85 @@ pub enum NodeSystemCmd {
         /// The section where this wallet is to be registered (NB: this is the section of the node id).
         section: XorName,
     },
+    /// Update the wallet registered for reward payouts.
+    UpdateWallet {
+        /// The new wallet to which rewards will be paid out by the network.
+        wallet: PublicKey,
+        /// The section where the wallet is registered (NB: this is the section of the node id).
+        section: XorName,
+        /// The version of the registration after the update, which must be greater than that
+        /// of the registered wallet, so that earlier updates cannot be replayed.
+        version: u64,
+        /// The node's Ed25519 signature over the new wallet, the section and the version.
+        proof: Ed25519Proof,
+    },
+}
+
+impl NodeSystemCmd {
+    /// Returns the bytes the node signs to update its wallet.
+    pub fn wallet_update_bytes(wallet: &PublicKey, section: &XorName, version: u64) -> Vec<u8> {
+        utils::serialise(&(wallet, section, version))
+    }
+
+    /// Verifies that an `UpdateWallet` cmd is signed by the node and is newer than the
+    /// registration at `current_version`, returning the id of that node.
+    ///
+    /// Returns `Err(InvalidSuccessor)` with the current version if the update is not newer.
+    pub fn verify_wallet_update(&self, current_version: u64) -> Result<XorName> {
+        match self {
+            Self::UpdateWallet {
+                wallet,
+                section,
+                version,
+                proof,
+            } => {
+                if !proof.verify(&Self::wallet_update_bytes(wallet, section, *version)) {
+                    return Err(Error::InvalidSignature);
+                }
+                if *version <= current_version {
+                    return Err(Error::InvalidSuccessor(current_version));
+                }
+                Ok(proof.id().into())
+            }
+            Self::RegisterWallet { .. } => Err(Error::InvalidOperation),
+        }
+    }
+
+    /// Checks that the section of the cmd is known, according to `is_known`.
+    ///
+    /// Returns the `WalletRegistration` error with `Error::NoSuchSection` otherwise.
+    pub fn check_section<F>(&self, is_known: F) -> std::result::Result<(), NodeCmdError>
+    where
+        F: Fn(&XorName) -> bool,
+    {
+        if is_known(self.section()) {
+            Ok(())
+        } else {
+            Err(self.error(Error::NoSuchSection))
+        }
+    }
+
+    /// Creates the error of the cmd.
+    pub fn error(&self, error: Error) -> NodeCmdError {
+        let (wallet, section) = match self {
+            Self::RegisterWallet { wallet, section }
+            | Self::UpdateWallet {
+                wallet, section, ..
+            } => (*wallet, *section),
+        };
+        NodeCmdError::System(NodeSystemError::WalletRegistration {
+            wallet,
+            section,
+            error,
+        })
+    }
+
+    /// Returns the section where the wallet is registered.
+    pub fn section(&self) -> &XorName {
+        match self {
+            Self::RegisterWallet { section, .. } | Self::UpdateWallet { section, .. } => section,
+        }
+    }
 }
 
 ///
@@ -65,6 +145,21 @@ pub enum NodeDataCmd {
         ///
         fetch_from_holders: BTreeSet<XorName>,
     },
+    /// Elder to Adult request to store a shard of an erasure coded Blob.
+    StoreShard {
+        /// The holder id.
+        holder: XorName,
+        /// The shard.
+        shard: BlobShard,
+    },
+    /// Elder to Adult request to delete a private chunk.
+    /// Public chunks cannot be deleted.
+    DeleteChunk {
+        /// The holder id.
+        holder: XorName,
+        /// The address of the private chunk.
+        address: BlobAddress,
+    },
 }
 
 // -------------- Node Events --------------
@@ -85,6 +180,61 @@ pub enum NodeEvent {
     },
     ///
     SectionPayoutValidated(TransferValidated),
+    /// Confirmation, from the section to the node,
+    /// that a wallet was registered or updated.
+    WalletRegistered {
+        /// The id of the node.
+        node_id: XorName,
+        /// The wallet registered for the node.
+        wallet: PublicKey,
+        /// The version of the registration, which the next update must exceed.
+        version: u64,
+    },
+    /// Wrapper for a deletion completion response, from a node to elders.
+    DeletionComplete {
+        ///
+        chunk: BlobAddress,
+        /// The Elder's accumulated signature
+        /// over the chunk address. This is sent back
+        /// to them so that any uninformed Elder knows
+        /// that the chunk was deleted.
+        proof: Signature,
+    },
+    /// Report, from a node to its section, of its storage capacity.
+    CapacityReport {
+        /// The id of the node.
+        node_id: XorName,
+        /// The storage capacity of the node.
+        capacity: StorageCapacity,
+    },
+    /// Notification, from a node to its section, that it cannot store any more chunks.
+    StorageFull {
+        /// The id of the node.
+        node_id: XorName,
+    },
+}
+
+/// Storage capacity of a node.
+#[derive(Debug, Hash, Eq, PartialEq, Clone, Copy, Serialize, Deserialize)]
+pub struct StorageCapacity {
+    /// Number of bytes used.
+    pub used: u64,
+    /// Total number of bytes available for storage.
+    pub total: u64,
+    /// Number of chunks held.
+    pub chunks: u64,
+}
+
+impl StorageCapacity {
+    /// Returns the number of bytes still free.
+    pub fn free(&self) -> u64 {
+        self.total.saturating_sub(self.used)
+    }
+
+    /// Returns true if a chunk of `size` bytes fits in the free space.
+    pub fn fits(&self, size: u64) -> bool {
+        size <= self.free()
+    }
 }
 
 ///
@@ -96,6 +246,20 @@ pub enum NodeQuery {
     Rewards(NodeRewardQuery),
     ///
     Transfers(NodeTransferQuery),
+    /// Queries related to the running of a node.
+    System(NodeSystemQuery),
+}
+
+/// Queries related to the running of a node.
+#[derive(Debug, Hash, Eq, PartialEq, Clone, Serialize, Deserialize)]
+pub enum NodeSystemQuery {
+    /// Get the wallet registered for reward payouts.
+    GetWallet {
+        /// The id of the node.
+        node_id: XorName,
+        /// The section where the wallet is registered (NB: this is the section of the node id).
+        section: XorName,
+    },
 }
 
 /// Reward query that is sent between sections.
@@ -112,6 +276,17 @@ pub enum NodeRewardQuery {
         /// in the new section.
         new_node_id: XorName,
     },
+    /// Sent by the new section to the
+    /// old section after node relocation,
+    /// to carry over the rewards of the node.
+    GetRelocatedRewards {
+        /// The id of the node
+        /// in the old section.
+        old_node_id: XorName,
+        /// The id of the node
+        /// in the new section.
+        new_node_id: XorName,
+    },
 }
 
 ///
@@ -140,6 +315,27 @@ pub enum NodeDataQuery {
         /// The chunk addresses.
         addresses: BTreeSet<BlobAddress>,
     },
+    /// Get a shard of an erasure coded Blob.
+    GetShard {
+        /// The holder id.
+        holder: XorName,
+        /// The shard address.
+        address: BlobShardAddress,
+    },
+    /// Elder to Adult challenge to prove that a chunk is still held.
+    GetStorageProof {
+        /// The holder id.
+        holder: XorName,
+        /// The chunk address.
+        address: BlobAddress,
+        /// Random challenge, so that proofs cannot be precomputed.
+        nonce: [u8; 32],
+    },
+    /// Elder to Adult query of its storage capacity.
+    GetCapacity {
+        /// The holder id.
+        holder: XorName,
+    },
 }
 
 ///
@@ -152,6 +348,15 @@ pub enum NodeQueryResponse {
     Rewards(NodeRewardQueryResponse),
     ///
     Transfers(NodeTransferQueryResponse),
+    ///
+    System(NodeSystemQueryResponse),
+}
+
+///
+#[derive(Debug, Hash, Eq, PartialEq, Clone, Serialize, Deserialize)]
+pub enum NodeSystemQueryResponse {
+    /// Returns the wallet registered for the node.
+    GetWallet(Result<PublicKey>),
 }
 
 ///
@@ -162,6 +367,10 @@ pub enum NodeRewardQueryResponse {
     /// together with the new node id,
     /// that followed with the original query.
     GetAccountId(Result<(PublicKey, XorName)>),
+    /// Returns the accumulated rewards and
+    /// registered wallet of the relocated node,
+    /// signed by the old section.
+    GetRelocatedRewards(Result<Proven<RelocatedRewards>>),
 }
 
 ///
@@ -182,6 +391,13 @@ pub enum NodeDataQueryResponse {
     GetChunk(Result<Blob>),
     /// Adult to Adult Get
     GetChunks(Result<Vec<Blob>>),
+    /// Returns a shard of an erasure coded Blob.
+    GetShard(Result<BlobShard>),
+    /// Returns the hash of the challenge nonce followed by the chunk value.
+    /// See `Blob::verify_storage_proof`.
+    GetStorageProof(Result<[u8; 32]>),
+    /// Returns the storage capacity of the holder.
+    GetCapacity(Result<StorageCapacity>),
 }
 
 ///
@@ -194,6 +410,22 @@ pub enum NodeCmdError {
     Rewards(NodeRewardError),
     ///
     Transfers(NodeTransferError),
+    ///
+    System(NodeSystemError),
+}
+
+///
+#[derive(Debug, Hash, Eq, PartialEq, Clone, Serialize, Deserialize)]
+pub enum NodeSystemError {
+    /// The error of a RegisterWallet or UpdateWallet cmd.
+    WalletRegistration {
+        ///
+        wallet: PublicKey,
+        ///
+        section: XorName,
+        ///
+        error: Error,
+    },
 }
 
 ///
@@ -206,6 +438,20 @@ pub enum NodeDataError {
         ///
         error: Error,
     },
+    /// The error of a StoreShard cmd.
+    ShardStorage {
+        ///
+        address: BlobShardAddress,
+        ///
+        error: Error,
+    },
+    /// The error of a DeleteChunk cmd.
+    ChunkDeletion {
+        ///
+        address: BlobAddress,
+        ///
+        error: Error,
+    },
 }
 
 ///
@@ -253,8 +499,10 @@ impl NodeCmd {
         use NodeDataCmd::*;
         use NodeTransferCmd::*;
         match self {
-            System(NodeSystemCmd::RegisterWallet { section, .. }) => Section(*section),
+            System(NodeSystemCmd::RegisterWallet { section, .. })
+            | System(NodeSystemCmd::UpdateWallet { section, .. }) => Section(*section),
             Data(DuplicateChunk { new_holder, .. }) => Node(*new_holder),
+            Data(DeleteChunk { holder, .. }) | Data(StoreShard { holder, .. }) => Node(*holder),
             Transfers(cmd) => match cmd {
                 ValidateSectionPayout(signed_transfer) => Section(signed_transfer.from().into()),
                 RegisterSectionPayout(debit_agreement) => Section(debit_agreement.from().into()),
@@ -270,8 +518,12 @@ impl NodeEvent {
         use Address::*;
         use NodeEvent::*;
         match self {
-            DuplicationComplete { chunk, .. } => Section(*chunk.name()),
+            DuplicationComplete { chunk, .. } | DeletionComplete { chunk, .. } => {
+                Section(*chunk.name())
+            }
             SectionPayoutValidated(event) => Section(event.from().into()),
+            CapacityReport { node_id, .. } | StorageFull { node_id } => Section(*node_id),
+            WalletRegistered { node_id, .. } => Node(*node_id),
         }
     }
 }
@@ -283,15 +535,22 @@ impl NodeQuery {
         use NodeDataQuery::*;
         use NodeQuery::*;
         use NodeRewardQuery::*;
+        use NodeSystemQuery::*;
         use NodeTransferQuery::*;
         match self {
+            System(GetWallet { section, .. }) => Section(*section),
             Data(data_query) => match data_query {
-                GetChunk { holder, .. } | GetChunks { holder, .. } => Node(*holder),
+                GetChunk { holder, .. }
+                | GetChunks { holder, .. }
+                | GetShard { holder, .. }
+                | GetCapacity { holder }
+                | GetStorageProof { holder, .. } => Node(*holder),
             },
             Transfers(transfer_query) => match transfer_query {
                 GetReplicaEvents(section_key) => Section((*section_key).into()),
             },
-            Rewards(GetAccountId { old_node_id, .. }) => Section(*old_node_id),
+            Rewards(GetAccountId { old_node_id, .. })
+            | Rewards(GetRelocatedRewards { old_node_id, .. }) => Section(*old_node_id),
         }
     }
 }
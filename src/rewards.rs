@@ -6,8 +6,12 @@
 // KIND, either express or implied. Please review the Licences for the specific language governing
 // permissions and limitations relating to use of the SAFE Network Software.
 
-use crate::Money;
+use crate::{
+    AccountId, DebitAgreementProof, Error, Money, NodeRewardError, Proven, PublicKey,
+    SignedTransfer, TransferId, XorName,
+};
 use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
 
 /// The representation of the smallest unit of work.
 /// This is strictly incrementing (i.e. accumulated)
@@ -49,3 +53,450 @@ impl Default for RewardCounter {
         }
     }
 }
+
+/// The rewards of a relocated node, carried over
+/// from its old section to its new section.
+#[derive(Clone, Eq, Hash, PartialEq, Debug, Serialize, Deserialize)]
+pub struct RelocatedRewards {
+    /// The id of the node in the old section.
+    pub old_node_id: XorName,
+    /// The id of the node in the new section.
+    pub new_node_id: XorName,
+    /// The wallet registered for reward payouts.
+    pub wallet: PublicKey,
+    /// The rewards accumulated in the old section.
+    pub counter: RewardCounter,
+}
+
+impl RelocatedRewards {
+    /// Validates rewards received from the old section of a relocated node,
+    /// given the key of that section and the ids of the node in the old and new sections.
+    pub fn validate(
+        proven: &Proven<Self>,
+        old_section: &PublicKey,
+        old_node_id: &XorName,
+        new_node_id: &XorName,
+    ) -> Result<(), Error> {
+        if proven.proof.id() != *old_section {
+            return Err(Error::AccessDenied);
+        }
+        if !proven.verify() {
+            return Err(Error::InvalidSignature);
+        }
+        if proven.value.old_node_id != *old_node_id || proven.value.new_node_id != *new_node_id {
+            return Err(Error::InvalidOperation);
+        }
+        Ok(())
+    }
+}
+
+/// The step a reward payout is at.
+#[derive(Clone, Copy, Eq, Hash, PartialEq, Debug, Serialize, Deserialize)]
+pub enum PayoutStage {
+    /// The accumulated reward has been claimed,
+    /// and a payout transfer is yet to be validated.
+    Claimed,
+    /// The payout transfer is being validated by the section Replicas.
+    Validating(TransferId),
+    /// The payout transfer has been agreed on, and is being registered.
+    Registering(TransferId),
+}
+
+/// A reward payout in progress, for a single account.
+#[derive(Clone, Eq, PartialEq, Debug, Serialize, Deserialize)]
+pub struct RewardPayout {
+    /// The account to which the reward is paid out.
+    account: AccountId,
+    /// The claimed reward.
+    counter: RewardCounter,
+    /// The current step of the payout.
+    stage: PayoutStage,
+    /// Number of failed attempts so far.
+    attempts: u32,
+}
+
+impl RewardPayout {
+    /// Returns the account to which the reward is paid out.
+    pub fn account(&self) -> AccountId {
+        self.account
+    }
+
+    /// Returns the claimed reward.
+    pub fn counter(&self) -> &RewardCounter {
+        &self.counter
+    }
+
+    /// Returns the current step of the payout.
+    pub fn stage(&self) -> PayoutStage {
+        self.stage
+    }
+
+    /// Returns the number of failed attempts so far.
+    pub fn attempts(&self) -> u32 {
+        self.attempts
+    }
+}
+
+/// The outcome of a failed payout step.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct PayoutFailure {
+    /// The error of the step that failed.
+    pub error: NodeRewardError,
+    /// Set when the payout ran out of attempts and was dropped.
+    /// It is then up to the caller to restore the reward to the worker.
+    pub dropped: Option<RewardCounter>,
+}
+
+/// Tracks reward payouts per account, as they move
+/// through claim -> validate -> register.
+///
+/// Transfers are deduplicated by their `TransferId`,
+/// so that a step arriving more than once
+/// is only applied the first time.
+/// Only the ids of payouts in progress, and the
+/// last registered payout per account, are kept.
+#[derive(Clone, Debug)]
+pub struct RewardPayouts {
+    /// Payouts in progress.
+    payouts: BTreeMap<AccountId, RewardPayout>,
+    /// Ids of the payout transfers in progress.
+    transfers: HashSet<TransferId>,
+    /// Id of the last payout transfer registered per account.
+    registered: BTreeMap<AccountId, TransferId>,
+    /// Max number of failed attempts before a payout is dropped.
+    max_attempts: u32,
+}
+
+impl RewardPayouts {
+    /// Creates a new tracker, dropping payouts
+    /// after `max_attempts` failed attempts.
+    pub fn new(max_attempts: u32) -> Self {
+        Self {
+            payouts: Default::default(),
+            transfers: Default::default(),
+            registered: Default::default(),
+            max_attempts,
+        }
+    }
+
+    /// Returns the payout in progress for the account, if any.
+    pub fn get(&self, account: &AccountId) -> Option<&RewardPayout> {
+        self.payouts.get(account)
+    }
+
+    /// Returns true if the payout transfer is the last one registered for its account.
+    pub fn is_registered(&self, id: &TransferId) -> bool {
+        self.registered.values().any(|registered| registered == id)
+    }
+
+    /// Starts a payout of the claimed reward to the account.
+    ///
+    /// Fails if there is nothing to pay out, or
+    /// a payout is already in progress for the account.
+    pub fn claim(
+        &mut self,
+        account: AccountId,
+        counter: RewardCounter,
+    ) -> Result<(), NodeRewardError> {
+        let error = if counter.reward == Money::zero() {
+            Error::InsufficientBalance
+        } else if self.payouts.contains_key(&account) {
+            Error::InvalidOperation
+        } else {
+            let _ = self.payouts.insert(
+                account,
+                RewardPayout {
+                    account,
+                    counter,
+                    stage: PayoutStage::Claimed,
+                    attempts: 0,
+                },
+            );
+            return Ok(());
+        };
+        Err(NodeRewardError::RewardClaiming {
+            account_id: account,
+            error,
+        })
+    }
+
+    /// Moves a claimed payout on to validation of its transfer.
+    ///
+    /// The transfer must pay out the claimed reward to the account.
+    /// Returns `Ok(false)` if the transfer has already been seen.
+    pub fn validate(&mut self, transfer: &SignedTransfer) -> Result<bool, NodeRewardError> {
+        let id = transfer.id();
+        let account = transfer.to();
+        if self.transfers.contains(&id) || self.registered.get(&account) == Some(&id) {
+            return Ok(false);
+        }
+        let error = match self.payouts.get_mut(&account) {
+            None => Error::NoSuchRecipient,
+            Some(payout) if payout.stage != PayoutStage::Claimed => Error::InvalidOperation,
+            Some(payout) if payout.counter.reward != transfer.amount() => Error::InvalidOperation,
+            Some(payout) => {
+                payout.stage = PayoutStage::Validating(id);
+                let _ = self.transfers.insert(id);
+                return Ok(true);
+            }
+        };
+        Err(NodeRewardError::RewardPayoutInitiation { id, account, error })
+    }
+
+    /// Moves a validated payout on to registration of its transfer.
+    ///
+    /// Returns `Ok(false)` if the registration is already in progress or done.
+    pub fn register(&mut self, proof: &DebitAgreementProof) -> Result<bool, NodeRewardError> {
+        let id = proof.id();
+        let account = proof.to();
+        let error = match self.payouts.get_mut(&account) {
+            Some(payout) if payout.stage == PayoutStage::Validating(id) => {
+                payout.stage = PayoutStage::Registering(id);
+                return Ok(true);
+            }
+            Some(payout) if payout.stage == PayoutStage::Registering(id) => return Ok(false),
+            _ if self.registered.get(&account) == Some(&id) => return Ok(false),
+            None => Error::NoSuchRecipient,
+            Some(_) => Error::InvalidOperation,
+        };
+        Err(NodeRewardError::RewardPayoutFinalisation { id, account, error })
+    }
+
+    /// Completes a payout once its transfer has been registered,
+    /// returning the reward that was paid out.
+    ///
+    /// Returns `None` if no payout is registering the transfer.
+    pub fn complete(&mut self, id: &TransferId) -> Option<RewardCounter> {
+        let account = self
+            .payouts
+            .values()
+            .find(|payout| payout.stage == PayoutStage::Registering(*id))
+            .map(|payout| payout.account)?;
+        let _ = self.transfers.remove(id);
+        let _ = self.registered.insert(account, *id);
+        self.payouts.remove(&account).map(|payout| payout.counter)
+    }
+
+    /// Records a failed attempt at the current step of the account's payout,
+    /// returning the corresponding error.
+    ///
+    /// A failed validation returns the payout to `Claimed`, so that the same
+    /// or a new transfer can be validated. Other steps are kept as they are, so that
+    /// they can be retried. Once `max_attempts` is reached, the payout is dropped.
+    pub fn fail(&mut self, account: &AccountId, error: Error) -> Option<PayoutFailure> {
+        let payout = self.payouts.get_mut(account)?;
+        payout.attempts += 1;
+        let error = match payout.stage {
+            PayoutStage::Claimed => NodeRewardError::RewardClaiming {
+                account_id: *account,
+                error,
+            },
+            PayoutStage::Validating(id) => {
+                payout.stage = PayoutStage::Claimed;
+                let _ = self.transfers.remove(&id);
+                NodeRewardError::RewardPayoutInitiation {
+                    id,
+                    account: *account,
+                    error,
+                }
+            }
+            PayoutStage::Registering(id) => NodeRewardError::RewardPayoutFinalisation {
+                id,
+                account: *account,
+                error,
+            },
+        };
+        let dropped = if payout.attempts >= self.max_attempts {
+            if let PayoutStage::Registering(id) = payout.stage {
+                let _ = self.transfers.remove(&id);
+            }
+            self.payouts.remove(account).map(|payout| payout.counter)
+        } else {
+            None
+        };
+        Some(PayoutFailure { error, dropped })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{BlsProof, Keypair, ReplicaPublicKeySet, Transfer};
+    use threshold_crypto::{SecretKey, SecretKeySet};
+    use unwrap::unwrap;
+
+    fn payout_transfer(to: AccountId, amount: Money) -> SignedTransfer {
+        let section = Keypair::new_ed25519(&mut rand::thread_rng());
+        let transfer = Transfer {
+            id: TransferId::new(section.public_key(), 0),
+            to,
+            amount,
+        };
+        let actor_signature = section.sign(&crate::utils::serialise(&transfer));
+        SignedTransfer {
+            transfer,
+            actor_signature,
+        }
+    }
+
+    fn agreement(signed_transfer: SignedTransfer) -> DebitAgreementProof {
+        let replicas = SecretKeySet::random(0, &mut rand::thread_rng());
+        let replica_key: ReplicaPublicKeySet = replicas.public_keys();
+        let debiting_replicas_sig = replicas
+            .secret_key()
+            .sign(&crate::utils::serialise(&signed_transfer))
+            .into();
+        DebitAgreementProof {
+            signed_transfer,
+            debiting_replicas_sig,
+            replica_key,
+        }
+    }
+
+    fn counter(nanos: u64) -> RewardCounter {
+        unwrap!(RewardCounter::default().add(Money::from_nano(nanos)))
+    }
+
+    #[test]
+    fn payout_moves_through_all_steps() {
+        let account = Keypair::new_ed25519(&mut rand::thread_rng()).public_key();
+        let mut payouts = RewardPayouts::new(3);
+        unwrap!(payouts.claim(account, counter(10)));
+
+        let transfer = payout_transfer(account, Money::from_nano(10));
+        let id = transfer.id();
+        assert!(unwrap!(payouts.validate(&transfer)));
+        assert!(!unwrap!(payouts.validate(&transfer)));
+
+        let proof = agreement(transfer);
+        assert!(unwrap!(payouts.register(&proof)));
+        assert!(!unwrap!(payouts.register(&proof)));
+
+        assert_eq!(payouts.complete(&id), Some(counter(10)));
+        assert_eq!(payouts.complete(&id), None);
+        assert!(payouts.is_registered(&id));
+        assert!(payouts.get(&account).is_none());
+        assert!(!unwrap!(payouts.register(&proof)));
+    }
+
+    #[test]
+    fn invalid_steps_are_rejected() {
+        let account = Keypair::new_ed25519(&mut rand::thread_rng()).public_key();
+        let mut payouts = RewardPayouts::new(3);
+        match payouts.claim(account, RewardCounter::default()) {
+            Err(NodeRewardError::RewardClaiming {
+                error: Error::InsufficientBalance,
+                ..
+            }) => (),
+            res => panic!("Unexpected result: {:?}", res),
+        }
+
+        unwrap!(payouts.claim(account, counter(10)));
+        match payouts.validate(&payout_transfer(account, Money::from_nano(9))) {
+            Err(NodeRewardError::RewardPayoutInitiation {
+                error: Error::InvalidOperation,
+                ..
+            }) => (),
+            res => panic!("Unexpected result: {:?}", res),
+        }
+        match payouts.register(&agreement(payout_transfer(account, Money::from_nano(10)))) {
+            Err(NodeRewardError::RewardPayoutFinalisation {
+                error: Error::InvalidOperation,
+                ..
+            }) => (),
+            res => panic!("Unexpected result: {:?}", res),
+        }
+    }
+
+    #[test]
+    fn failed_steps_are_retried_until_dropped() {
+        let account = Keypair::new_ed25519(&mut rand::thread_rng()).public_key();
+        let mut payouts = RewardPayouts::new(2);
+        unwrap!(payouts.claim(account, counter(10)));
+
+        let transfer = payout_transfer(account, Money::from_nano(10));
+        assert!(unwrap!(payouts.validate(&transfer)));
+        let failure = unwrap!(payouts.fail(&account, Error::InvalidSignature));
+        match failure.error {
+            NodeRewardError::RewardPayoutInitiation { id, .. } => assert_eq!(id, transfer.id()),
+            error => panic!("Unexpected error: {:?}", error),
+        }
+        assert_eq!(failure.dropped, None);
+        assert_eq!(unwrap!(payouts.get(&account)).stage(), PayoutStage::Claimed);
+
+        let transfer = payout_transfer(account, Money::from_nano(10));
+        assert!(unwrap!(payouts.validate(&transfer)));
+        assert!(unwrap!(payouts.register(&agreement(transfer))));
+        let failure = unwrap!(payouts.fail(&account, Error::InvalidSignature));
+        match failure.error {
+            NodeRewardError::RewardPayoutFinalisation { .. } => (),
+            error => panic!("Unexpected error: {:?}", error),
+        }
+        assert_eq!(failure.dropped, Some(counter(10)));
+        assert!(payouts.get(&account).is_none());
+    }
+
+    #[test]
+    fn failed_transfer_can_be_retried() {
+        let account = Keypair::new_ed25519(&mut rand::thread_rng()).public_key();
+        let mut payouts = RewardPayouts::new(3);
+        unwrap!(payouts.claim(account, counter(10)));
+
+        let transfer = payout_transfer(account, Money::from_nano(10));
+        let id = transfer.id();
+        assert!(unwrap!(payouts.validate(&transfer)));
+        let _ = unwrap!(payouts.fail(&account, Error::InvalidSignature));
+        assert!(unwrap!(payouts.validate(&transfer)));
+
+        assert!(unwrap!(payouts.register(&agreement(transfer.clone()))));
+        assert_eq!(payouts.complete(&id), Some(counter(10)));
+        assert!(payouts.transfers.is_empty());
+        assert!(!unwrap!(payouts.validate(&transfer)));
+    }
+
+    #[test]
+    fn relocated_rewards_are_validated() {
+        let old_section = SecretKey::random();
+        let rewards = RelocatedRewards {
+            old_node_id: XorName::random(),
+            new_node_id: XorName::random(),
+            wallet: Keypair::new_ed25519(&mut rand::thread_rng()).public_key(),
+            counter: counter(10),
+        };
+        let proof = BlsProof {
+            public_key: old_section.public_key(),
+            signature: old_section.sign(&crate::utils::serialise(&rewards)),
+        };
+        let old_node_id = rewards.old_node_id;
+        let new_node_id = rewards.new_node_id;
+        let section_key = PublicKey::Bls(old_section.public_key());
+        let proven = Proven::new(rewards, proof);
+        unwrap!(RelocatedRewards::validate(
+            &proven,
+            &section_key,
+            &old_node_id,
+            &new_node_id
+        ));
+
+        let other_key = PublicKey::Bls(SecretKey::random().public_key());
+        assert_eq!(
+            RelocatedRewards::validate(&proven, &other_key, &old_node_id, &new_node_id),
+            Err(Error::AccessDenied)
+        );
+        assert_eq!(
+            RelocatedRewards::validate(&proven, &section_key, &old_node_id, &XorName::random()),
+            Err(Error::InvalidOperation)
+        );
+        assert_eq!(
+            RelocatedRewards::validate(&proven, &section_key, &XorName::random(), &new_node_id),
+            Err(Error::InvalidOperation)
+        );
+
+        let mut tampered = proven;
+        tampered.value.counter = counter(1_000);
+        assert_eq!(
+            RelocatedRewards::validate(&tampered, &section_key, &old_node_id, &new_node_id),
+            Err(Error::InvalidSignature)
+        );
+    }
+}
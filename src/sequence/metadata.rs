@@ -8,6 +8,7 @@
 // Software.
 
 use crate::{utils, Error, PublicKey, Result, XorName};
+use bytes::Bytes;
 use multibase::Decodable;
 use serde::{Deserialize, Serialize};
 use std::{collections::BTreeMap, fmt::Debug, hash::Hash};
@@ -26,8 +27,8 @@ pub enum Action {
 /// List of entries.
 pub type Entries = Vec<Entry>;
 
-/// An entry in a Sequence.
-pub type Entry = Vec<u8>;
+/// An entry in a Sequence. It is reference counted, so cloning it is cheap.
+pub type Entry = Bytes;
 
 /// Address of a Sequence.
 #[derive(Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Hash, Serialize, Deserialize, Debug)]
@@ -170,7 +170,7 @@ impl Data {
     }
 
     /// Returns a value at 'index', if present.
-    pub fn get(&self, index: Index) -> Option<&Vec<u8>> {
+    pub fn get(&self, index: Index) -> Option<&Entry> {
         match self {
             Data::Public(data) => data.get(index),
             Data::Private(data) => data.get(index),
@@ -361,8 +361,8 @@ impl From<PrivSeqData> for Data {
 #[cfg(test)]
 mod tests {
     use crate::{
-        Error, PublicKey, Result, Sequence, SequenceAddress, SequenceIndex, SequenceKind,
-        SequencePrivUserPermissions, SequencePubUserPermissions, SequenceUser,
+        Error, PublicKey, Result, Sequence, SequenceAddress, SequenceEntry, SequenceIndex,
+        SequenceKind, SequencePrivUserPermissions, SequencePubUserPermissions, SequenceUser,
         SequenceUserPermissions, XorName,
     };
     use std::collections::BTreeMap;
@@ -414,8 +414,8 @@ mod tests {
         let mut replica1 = Sequence::new_pub(actor, sequence_name, sequence_tag);
         let mut replica2 = Sequence::new_pub(actor, sequence_name, sequence_tag);
 
-        let entry1 = b"value0".to_vec();
-        let entry2 = b"value1".to_vec();
+        let entry1 = SequenceEntry::from_static(b"value0");
+        let entry2 = SequenceEntry::from_static(b"value1");
 
         let op1 = replica1.append(entry1.clone());
         let op2 = replica1.append(entry2.clone());